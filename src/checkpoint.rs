@@ -0,0 +1,185 @@
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::output::Output;
+
+/// How often the read position is written out.
+const INTERVAL: Duration = Duration::from_secs(5);
+
+/// How far into the inputs a run has got.
+#[derive(Clone, Debug)]
+pub struct Position {
+    /// Index of the source in the order the inputs were given.
+    pub source: usize,
+    /// The source's name, used to make sure a resumed run reads the same inputs.
+    pub name: String,
+    /// Bytes of the (decompressed) source that have been parsed.
+    pub offset: u64,
+    /// The root domains already written when only unique ones are wanted.
+    /// They are kept one per line in a file next to the checkpoint, which
+    /// each save only adds the newly written ones to.
+    pub seen: Vec<String>,
+}
+
+/// Where the root domains written so far are kept.
+fn seen_path(path: &Path) -> PathBuf {
+    let mut seen = path.as_os_str().to_owned();
+    seen.push(".seen");
+    PathBuf::from(seen)
+}
+
+/// Removes the checkpoint of a finished run.
+pub fn remove(path: &Path) {
+    let _ = std::fs::remove_file(path);
+    let _ = std::fs::remove_file(seen_path(path));
+}
+
+/// Reads the position saved by an earlier run.
+pub fn load(path: &Path) -> Result<Position, Box<dyn Error + Send + Sync + 'static>> {
+    let data = match std::fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) => {
+            return Err(format!("could not read checkpoint {}: {}", path.display(), e).into())
+        }
+    };
+    let value: Value = match serde_json::from_str(&data) {
+        Ok(value) => value,
+        Err(e) => return Err(format!("invalid checkpoint {}: {}", path.display(), e).into()),
+    };
+    match (
+        value["source"].as_u64(),
+        value["name"].as_str(),
+        value["offset"].as_u64(),
+    ) {
+        (Some(source), Some(name), Some(offset)) => Ok(Position {
+            source: source as usize,
+            name: name.to_string(),
+            offset,
+            // a crash while adding to it can leave the last line cut short
+            seen: std::fs::read_to_string(seen_path(path))
+                .map(|seen| {
+                    seen.split_inclusive('\n')
+                        .filter_map(|root| root.strip_suffix('\n'))
+                        .map(|root| root.to_string())
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }),
+        _ => Err(format!("invalid checkpoint {}", path.display()).into()),
+    }
+}
+
+/// Writes the position next to the checkpoint and renames it into place, so
+/// a crash half way through never leaves a truncated checkpoint behind.
+fn save(path: &Path, position: &Position) -> std::io::Result<()> {
+    let value = json!({
+        "source": position.source,
+        "name": position.name,
+        "offset": position.offset,
+    });
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, value.to_string())?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Adds the root domains written since the last save to the ones kept next
+/// to the checkpoint, so saving costs what was written since rather than
+/// everything written so far.
+fn add_seen(path: &Path, roots: &[String]) -> std::io::Result<()> {
+    if roots.is_empty() {
+        return Ok(());
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(seen_path(path))?;
+    let mut data = String::new();
+    for root in roots {
+        data.push_str(root);
+        data.push('\n');
+    }
+    file.write_all(data.as_bytes())?;
+    file.sync_data()
+}
+
+/// Periodically saves the position of the last line whose hosts have all
+/// been parsed. Lines are read well ahead of the workers, so every saved
+/// position waits until the workers have caught up with it.
+pub struct Checkpointer {
+    path: PathBuf,
+    done: Arc<AtomicU64>,
+    output: Arc<Output>,
+    sent: u64,
+    marks: VecDeque<(u64, Position)>,
+    last: Instant,
+}
+
+impl Checkpointer {
+    /// Starts checkpointing to `path`, adding to the root domains saved by
+    /// an earlier run when `resume` is set.
+    pub fn new(
+        path: PathBuf,
+        done: Arc<AtomicU64>,
+        output: Arc<Output>,
+        resume: bool,
+    ) -> Checkpointer {
+        if !resume {
+            let _ = std::fs::remove_file(seen_path(&path));
+        }
+        Checkpointer {
+            path,
+            done,
+            output,
+            sent: 0,
+            marks: VecDeque::new(),
+            last: Instant::now(),
+        }
+    }
+
+    /// Records that `jobs` more jobs have been queued.
+    pub fn sent(&mut self, jobs: u64) {
+        self.sent += jobs;
+    }
+
+    /// Records that everything up to `offset` in a source has been queued.
+    pub fn mark(&mut self, source: usize, name: &dyn std::fmt::Display, offset: u64) {
+        if self.last.elapsed() >= INTERVAL {
+            self.last = Instant::now();
+            self.marks.push_back((
+                self.sent,
+                Position {
+                    source,
+                    name: name.to_string(),
+                    offset,
+                    seen: vec![],
+                },
+            ));
+        }
+
+        let done = self.done.load(Ordering::Relaxed);
+        let mut ready = None;
+        while self.marks.front().is_some_and(|(sent, _)| *sent <= done) {
+            ready = self.marks.pop_front().map(|(_, position)| position);
+        }
+        if let Some(position) = ready {
+            let seen = self.output.fresh();
+            // what the position covers has to be on disk before it is saved
+            self.output.sync();
+            match add_seen(&self.path, &seen).and_then(|()| save(&self.path, &position)) {
+                Ok(()) => info!(
+                    "saved checkpoint at byte {} of {}",
+                    position.offset, position.name
+                ),
+                Err(e) => warn!("could not write checkpoint {}: {}", self.path.display(), e),
+            }
+        }
+    }
+}
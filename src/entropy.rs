@@ -0,0 +1,107 @@
+/// The hundred most common letter pairs of english text, most common first.
+const BIGRAMS: [&str; 100] = [
+    "th", "he", "in", "er", "an", "re", "on", "at", "en", "nd", "ti", "es", "or", "te", "of", "ed",
+    "is", "it", "al", "ar", "st", "to", "nt", "ng", "se", "ha", "as", "ou", "io", "le", "ve", "co",
+    "me", "de", "hi", "ri", "ro", "ic", "ne", "ea", "ra", "ce", "li", "ch", "ll", "be", "ma", "si",
+    "om", "ur", "ca", "el", "ta", "la", "ns", "di", "fo", "ho", "pe", "ec", "pr", "no", "ct", "us",
+    "ac", "ot", "il", "tr", "ly", "nc", "et", "ut", "ss", "so", "rs", "un", "lo", "wa", "ge", "ie",
+    "wh", "ee", "wi", "em", "ad", "ol", "rt", "po", "we", "na", "ul", "ni", "ts", "mo", "ow", "pa",
+    "im", "mi", "ai", "sh",
+];
+
+/// Labels shorter than this are too short to tell generated from chosen.
+const MIN_LENGTH: usize = 8;
+
+/// Labels with fewer common letter pairs than this share are unlike words.
+const MAX_NGRAM: f64 = 0.4;
+
+/// What to do with hosts that look machine generated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Entropy {
+    /// Print every host with its scores.
+    Tag,
+    /// Leave them out.
+    Drop,
+    /// Only print them.
+    Only,
+}
+
+impl Entropy {
+    pub fn new(name: &str) -> Entropy {
+        match name {
+            "drop" => Entropy::Drop,
+            "only" => Entropy::Only,
+            _ => Entropy::Tag,
+        }
+    }
+}
+
+/// How random the most random label of a name looks: the Shannon entropy
+/// of its characters in bits, and the share of its letter pairs that are
+/// common in english.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Score {
+    pub entropy: f64,
+    pub ngram: f64,
+    length: usize,
+}
+
+impl Score {
+    /// Scores the labels of a name, keeping the one with the most entropy.
+    pub fn new(name: &str) -> Score {
+        name.split('.')
+            .filter(|label| !label.is_empty())
+            .map(|label| Score {
+                entropy: entropy(label),
+                ngram: ngram(label),
+                length: label.chars().count(),
+            })
+            .fold(Score::default(), |best, score| {
+                if score.entropy > best.entropy {
+                    score
+                } else {
+                    best
+                }
+            })
+    }
+
+    /// Whether the label looks machine generated, long and random with few
+    /// of the letter pairs of words.
+    pub fn is_dga(&self, threshold: f64) -> bool {
+        self.length >= MIN_LENGTH && self.entropy >= threshold && self.ngram < MAX_NGRAM
+    }
+}
+
+/// The Shannon entropy of the characters of a label, in bits.
+fn entropy(label: &str) -> f64 {
+    let mut counts = std::collections::HashMap::new();
+    let mut total: f64 = 0.0;
+    for c in label.chars() {
+        *counts.entry(c.to_ascii_lowercase()).or_insert(0.0f64) += 1.0;
+        total += 1.0;
+    }
+    counts
+        .values()
+        .map(|count| {
+            let p = count / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// The share of the pairs of neighbouring characters of a label that are
+/// among the common english ones.
+fn ngram(label: &str) -> f64 {
+    let chars: Vec<char> = label.chars().map(|c| c.to_ascii_lowercase()).collect();
+    if chars.len() < 2 {
+        return 1.0;
+    }
+    let common = chars
+        .windows(2)
+        .filter(|pair| {
+            let pair: String = pair.iter().collect();
+            BIGRAMS.contains(&pair.as_str())
+        })
+        .count();
+    common as f64 / (chars.len() - 1) as f64
+}
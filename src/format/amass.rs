@@ -0,0 +1,37 @@
+use serde_json::Value;
+
+use super::Host;
+
+/// Returns the name from a line of amass JSON output, along with the data
+/// sources that reported it when asked for.
+pub fn hosts(line: &str, sources: bool) -> Vec<Host> {
+    let record = match serde_json::from_str::<Value>(line) {
+        Ok(record) => record,
+        Err(_) => return vec![],
+    };
+
+    let name = match record["name"].as_str() {
+        Some(name) => name.to_string(),
+        None => return vec![],
+    };
+
+    let sources = if sources {
+        record["sources"]
+            .as_array()
+            .map(|sources| {
+                sources
+                    .iter()
+                    .filter_map(|source| source.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    } else {
+        vec![]
+    };
+
+    vec![Host {
+        name,
+        sources,
+        mailbox: None,
+    }]
+}
@@ -0,0 +1,93 @@
+use base64::Engine;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+/// Returns the hosts of every item in a Burp Suite XML export, taken from the
+/// request url, the `host` element and the Host header of the raw request.
+/// Each host is emitted once per item.
+pub fn hosts(text: &str) -> Vec<String> {
+    let mut reader = Reader::from_str(text);
+    let mut hosts = Vec::new();
+    let mut item = Vec::new();
+    let mut element = Vec::new();
+    let mut base64 = false;
+    let mut content = String::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                element = e.name().as_ref().to_vec();
+                base64 = matches!(
+                    e.try_get_attribute("base64"),
+                    Ok(Some(attr)) if attr.value.as_ref() == b"true"
+                );
+                content.clear();
+            }
+            Ok(Event::Text(e)) => {
+                if let Ok(text) = e.unescape() {
+                    content.push_str(&text);
+                }
+            }
+            Ok(Event::CData(e)) => content.push_str(&String::from_utf8_lossy(&e)),
+            Ok(Event::End(e)) => {
+                let found = match element.as_slice() {
+                    b"url" => url_host(content.trim()),
+                    b"host" if !content.trim().is_empty() => Some(content.trim().to_string()),
+                    b"request" => request_host(&content, base64),
+                    _ => None,
+                };
+                if let Some(host) = found {
+                    if !item.contains(&host) {
+                        item.push(host);
+                    }
+                }
+                if e.name().as_ref() == b"item" {
+                    hosts.append(&mut item);
+                }
+                element.clear();
+                content.clear();
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                warn!("error parsing burp xml: {}", e);
+                break;
+            }
+            _ => continue,
+        }
+    }
+    hosts
+}
+
+fn url_host(url: &str) -> Option<String> {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_string()))
+}
+
+/// Reads the Host header from a raw, optionally base64 encoded, http request.
+fn request_host(request: &str, base64: bool) -> Option<String> {
+    let raw = if base64 {
+        match base64::engine::general_purpose::STANDARD.decode(request.trim()) {
+            Ok(raw) => String::from_utf8_lossy(&raw).into_owned(),
+            Err(_) => return None,
+        }
+    } else {
+        request.to_string()
+    };
+
+    raw.lines()
+        .skip(1)
+        .take_while(|line| !line.trim().is_empty())
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            if !name.trim().eq_ignore_ascii_case("host") {
+                return None;
+            }
+            // drop any port
+            let value = value.trim();
+            let host = match value.rsplit_once(':') {
+                Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+                _ => value,
+            };
+            Some(host.to_string())
+        })
+}
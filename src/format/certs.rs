@@ -0,0 +1,52 @@
+use x509_parser::certificate::X509Certificate;
+use x509_parser::extensions::GeneralName;
+use x509_parser::pem::Pem;
+use x509_parser::prelude::FromDer;
+
+/// Returns the subject common names and SAN dNSNames of every certificate
+/// in a PEM bundle or a run of concatenated DER certificates. Certificates
+/// that fail to parse are skipped.
+pub fn hosts(data: &[u8]) -> Vec<String> {
+    let mut hosts = Vec::new();
+    if data.windows(11).any(|window| window == b"-----BEGIN ") {
+        for pem in Pem::iter_from_buffer(data).flatten() {
+            if pem.label != "CERTIFICATE" && pem.label != "X509 CERTIFICATE" {
+                continue;
+            }
+            if let Ok(cert) = pem.parse_x509() {
+                hosts.extend(names(&cert));
+            }
+        }
+    } else {
+        let mut rest = data;
+        while !rest.is_empty() {
+            match X509Certificate::from_der(rest) {
+                Ok((remaining, cert)) => {
+                    hosts.extend(names(&cert));
+                    rest = remaining;
+                }
+                Err(_) => break,
+            }
+        }
+    }
+    hosts
+}
+
+fn names(cert: &X509Certificate) -> Vec<String> {
+    let mut names: Vec<String> = cert
+        .subject()
+        .iter_common_name()
+        .filter_map(|name| name.as_str().ok())
+        .map(|name| name.to_string())
+        .collect();
+    if let Ok(Some(san)) = cert.subject_alternative_name() {
+        for name in &san.value.general_names {
+            if let GeneralName::DNSName(name) = name {
+                if !names.iter().any(|known| known == name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+    }
+    names
+}
@@ -0,0 +1,80 @@
+/// A csv column, either by header name or by zero based index.
+#[derive(Clone, Debug)]
+pub enum Column {
+    Name(String),
+    Index(usize),
+}
+
+/// Per source csv state: the header still to be read and any record whose
+/// quoted field spans several lines.
+#[derive(Clone, Debug)]
+pub struct CsvState {
+    column: Column,
+    header: bool,
+    index: Option<usize>,
+    pending: String,
+}
+
+impl CsvState {
+    pub fn new(column: &str, header: bool) -> CsvState {
+        let column = match column.parse::<usize>() {
+            Ok(i) => Column::Index(i),
+            Err(_) => Column::Name(column.to_string()),
+        };
+        let index = match column {
+            Column::Index(i) => Some(i),
+            Column::Name(_) => None,
+        };
+        CsvState {
+            column,
+            header,
+            index,
+            pending: String::new(),
+        }
+    }
+
+    /// Returns the host of the record ending on this line.
+    pub fn hosts(&mut self, line: &str) -> Vec<String> {
+        if !self.pending.is_empty() {
+            self.pending.push('\n');
+        }
+        self.pending.push_str(line);
+
+        // an odd number of quotes means a quoted field continues on the next line
+        if self.pending.matches('"').count() % 2 == 1 {
+            return vec![];
+        }
+
+        let text = std::mem::take(&mut self.pending);
+        let mut reader = ::csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(text.as_bytes());
+        let record = match reader.records().next() {
+            Some(Ok(record)) => record,
+            _ => return vec![],
+        };
+
+        if self.header {
+            self.header = false;
+            if let Column::Name(name) = &self.column {
+                self.index = record
+                    .iter()
+                    .position(|field| field.trim_start_matches('\u{feff}').trim() == name);
+                if self.index.is_none() {
+                    warn!("column {} not found in csv header", name);
+                }
+            }
+            return vec![];
+        }
+
+        match self
+            .index
+            .and_then(|i| record.get(i))
+            .map(|field| field.trim())
+        {
+            Some(host) if !host.is_empty() => vec![host.to_string()],
+            _ => vec![],
+        }
+    }
+}
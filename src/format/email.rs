@@ -0,0 +1,27 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+use super::Host;
+
+/// Matches an email address, capturing the mailbox and the domain.
+const EMAIL_PATTERN: &str =
+    r"(?i)([a-z0-9._%+-]+)@((?:[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?\.)+[a-z][a-z0-9-]*[a-z0-9])";
+
+fn email_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(EMAIL_PATTERN).unwrap())
+}
+
+/// Returns the domains of the email addresses on a line, which may be a bare
+/// address, a `Name <address>` contact or an `address:password` combo.
+/// The mailbox is carried along when `mailbox` is set.
+pub fn hosts(line: &str, mailbox: bool) -> Vec<Host> {
+    email_regex()
+        .captures_iter(line)
+        .map(|captures| Host {
+            name: captures[2].to_string(),
+            sources: vec![],
+            mailbox: mailbox.then(|| captures[1].to_string()),
+        })
+        .collect()
+}
@@ -0,0 +1,20 @@
+use std::net::IpAddr;
+
+/// Returns the hostnames from a line of an /etc/hosts style file such as
+/// `0.0.0.0 ads.example.com tracker.example.com # blocked`, skipping the
+/// address and any names that are addresses themselves.
+pub fn hosts(line: &str) -> Vec<String> {
+    let line = match line.split_once('#') {
+        Some((line, _)) => line,
+        None => line,
+    };
+    let mut fields = line.split_whitespace();
+    match fields.next() {
+        Some(address) if address.parse::<IpAddr>().is_ok() => fields
+            .filter(|name| name.parse::<IpAddr>().is_err())
+            .map(|name| name.trim_end_matches('.').to_string())
+            .filter(|name| !name.is_empty())
+            .collect(),
+        _ => vec![],
+    }
+}
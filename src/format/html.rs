@@ -0,0 +1,53 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Matches the attributes that hold links, with a double quoted, single
+/// quoted or bare value.
+const ATTRIBUTE_PATTERN: &str = r#"(?i)\b(?:href|src|srcset|action|formaction|poster|cite|data-src|content)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#;
+
+fn attribute_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(ATTRIBUTE_PATTERN).unwrap())
+}
+
+/// Returns the hosts of the absolute links in an html document, taken from
+/// href, src, action and similar attributes as well as meta tags such as
+/// refresh redirects and og:url. Relative links are skipped and each host
+/// is emitted once.
+pub fn hosts(text: &str) -> Vec<String> {
+    let mut hosts: Vec<String> = Vec::new();
+    for captures in attribute_regex().captures_iter(text) {
+        let value = match captures.get(1).or(captures.get(2)).or(captures.get(3)) {
+            Some(value) => value.as_str(),
+            None => continue,
+        };
+        // srcset lists several candidates, meta refresh puts the url after `url=`
+        for link in value.split([',', ';']) {
+            let link = link.trim();
+            let link = match link.get(..4) {
+                Some(prefix) if prefix.eq_ignore_ascii_case("url=") => &link[4..],
+                _ => link,
+            };
+            let link = link.split_whitespace().next().unwrap_or("");
+            if let Some(host) = link_host(link) {
+                if !hosts.contains(&host) {
+                    hosts.push(host);
+                }
+            }
+        }
+    }
+    hosts
+}
+
+/// Returns the host of an absolute or scheme relative link.
+fn link_host(link: &str) -> Option<String> {
+    let link = link.trim_matches(|c| c == '"' || c == '\'');
+    let url = if link.starts_with("//") {
+        url::Url::parse(&format!("https:{}", link)).ok()?
+    } else if link.contains("://") {
+        url::Url::parse(link).ok()?
+    } else {
+        return None;
+    };
+    url.host_str().map(|host| host.to_string())
+}
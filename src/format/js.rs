@@ -0,0 +1,111 @@
+use super::text;
+
+/// Returns the hosts found in the string literals of a javascript file,
+/// from urls as well as bare hostnames, each emitted once. Comments are
+/// skipped.
+pub fn hosts(source: &str) -> Vec<String> {
+    let mut hosts: Vec<String> = Vec::new();
+    for literal in literals(source) {
+        for host in text::hostnames(&unescape(&literal)) {
+            if !hosts.contains(&host) {
+                hosts.push(host);
+            }
+        }
+    }
+    hosts
+}
+
+/// Returns the raw contents of the single quoted, double quoted and
+/// template literals of the source, with their escapes left in.
+fn literals(source: &str) -> Vec<String> {
+    let mut literals = vec![];
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut star = false;
+                for c in chars.by_ref() {
+                    if star && c == '/' {
+                        break;
+                    }
+                    star = c == '*';
+                }
+            }
+            '"' | '\'' | '`' => {
+                let mut literal = String::new();
+                while let Some(next) = chars.next() {
+                    match next {
+                        '\\' => {
+                            literal.push(next);
+                            if let Some(escaped) = chars.next() {
+                                literal.push(escaped);
+                            }
+                        }
+                        // only template literals run on over several lines
+                        '\n' if c != '`' => break,
+                        next if next == c => break,
+                        next => literal.push(next),
+                    }
+                }
+                literals.push(literal);
+            }
+            _ => {}
+        }
+    }
+    literals
+}
+
+/// Resolves the escapes of a literal, such as the `\/` of urls in json and
+/// `\x2e` or `\u002e` for a dot. Line breaks and tabs become spaces so the
+/// words around them stay apart.
+fn unescape(literal: &str) -> String {
+    let mut text = String::with_capacity(literal.len());
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        let escaped = match chars.next() {
+            Some(escaped) => escaped,
+            None => break,
+        };
+        let code = match escaped {
+            'n' | 'r' | 't' => {
+                text.push(' ');
+                continue;
+            }
+            'x' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                u32::from_str_radix(&hex, 16).ok()
+            }
+            'u' => {
+                let mut rest = chars.clone();
+                let hex: String = if rest.next() == Some('{') {
+                    let hex: String = rest.by_ref().take_while(|c| *c != '}').collect();
+                    chars = rest;
+                    hex
+                } else {
+                    chars.by_ref().take(4).collect()
+                };
+                u32::from_str_radix(&hex, 16).ok()
+            }
+            escaped => {
+                text.push(escaped);
+                continue;
+            }
+        };
+        if let Some(c) = code.and_then(char::from_u32) {
+            text.push(c);
+        }
+    }
+    text
+}
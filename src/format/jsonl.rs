@@ -0,0 +1,37 @@
+use serde_json::Value;
+
+/// Follows a dotted field path through a JSON object, array elements are
+/// addressed by index. A string value yields one host, an array of strings
+/// yields one host per element.
+pub fn hosts(line: &str, path: &[String]) -> Vec<String> {
+    let mut value = match serde_json::from_str::<Value>(line) {
+        Ok(value) => value,
+        Err(_) => return vec![],
+    };
+
+    for key in path {
+        value = match value {
+            Value::Object(mut map) => match map.remove(key) {
+                Some(value) => value,
+                None => return vec![],
+            },
+            Value::Array(mut items) => match key.parse::<usize>() {
+                Ok(i) if i < items.len() => items.swap_remove(i),
+                _ => return vec![],
+            },
+            _ => return vec![],
+        };
+    }
+
+    match value {
+        Value::String(host) => vec![host],
+        Value::Array(items) => items
+            .into_iter()
+            .filter_map(|item| match item {
+                Value::String(host) => Some(host),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
@@ -0,0 +1,63 @@
+use serde_json::Value;
+
+use super::text;
+
+/// Returns the hosts found on a line of masscan `-oJ` or `-oL` output: any
+/// hostnames in service banners and, when asked for, the scanned address.
+pub fn hosts(line: &str, ips: bool) -> Vec<String> {
+    let line = line.trim();
+    if line.starts_with('{') {
+        json_hosts(line.trim_end_matches(','), ips)
+    } else {
+        list_hosts(line, ips)
+    }
+}
+
+/// Parses one record of `-oJ` output, e.g.
+/// `{"ip": "10.0.0.1", "ports": [{"port": 443, "service": {"banner": "..."}}]}`
+fn json_hosts(line: &str, ips: bool) -> Vec<String> {
+    let record = match serde_json::from_str::<Value>(line) {
+        Ok(record) => record,
+        Err(_) => return vec![],
+    };
+
+    let mut hosts = Vec::new();
+    if ips {
+        if let Some(ip) = record["ip"].as_str() {
+            hosts.push(ip.to_string());
+        }
+    }
+    if let Some(ports) = record["ports"].as_array() {
+        for port in ports {
+            if let Some(banner) = port["service"]["banner"].as_str() {
+                hosts.extend(text::hostnames(banner));
+            }
+        }
+    }
+    hosts
+}
+
+/// Parses one record of `-oL` output, e.g.
+/// `open tcp 443 10.0.0.1 1588888888` or
+/// `banner tcp 443 10.0.0.1 1588888888 ssl TLS/1.1 cipher:0xc013, www.example.com`
+fn list_hosts(line: &str, ips: bool) -> Vec<String> {
+    if line.is_empty() || line.starts_with('#') {
+        return vec![];
+    }
+
+    let fields: Vec<&str> = line.splitn(7, ' ').collect();
+    if fields.len() < 4 {
+        return vec![];
+    }
+
+    let mut hosts = Vec::new();
+    if ips {
+        hosts.push(fields[3].to_string());
+    }
+    if fields[0] == "banner" {
+        if let Some(banner) = fields.get(6) {
+            hosts.extend(text::hostnames(banner));
+        }
+    }
+    hosts
+}
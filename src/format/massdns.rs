@@ -0,0 +1,15 @@
+/// Returns the queried name from a line of massdns simple output such as
+/// `www.example.com. A 93.184.216.34`, without the trailing dot.
+pub fn hosts(line: &str) -> Vec<String> {
+    match line.split_whitespace().next() {
+        Some(name) if !name.starts_with(';') => {
+            let name = name.trim_end_matches('.');
+            if name.is_empty() {
+                vec![]
+            } else {
+                vec![name.to_string()]
+            }
+        }
+        _ => vec![],
+    }
+}
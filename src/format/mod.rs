@@ -0,0 +1,201 @@
+mod amass;
+mod burp;
+mod certs;
+mod csv;
+mod email;
+mod hostsfile;
+mod html;
+mod js;
+mod jsonl;
+mod masscan;
+mod massdns;
+mod nmap;
+mod sitemap;
+mod text;
+mod zone;
+
+pub use self::csv::CsvState;
+pub use self::zone::ZoneState;
+
+/// A host read from the input, with any metadata the format carries along.
+#[derive(Clone, Debug)]
+pub struct Host {
+    pub name: String,
+    pub sources: Vec<String>,
+    /// The mailbox of an email address, printed in front of its root domain.
+    pub mailbox: Option<String>,
+}
+
+impl From<String> for Host {
+    fn from(name: String) -> Host {
+        Host {
+            name,
+            sources: vec![],
+            mailbox: None,
+        }
+    }
+}
+
+/// Options used by the formats that need them.
+#[derive(Clone, Debug, Default)]
+pub struct FormatOptions {
+    /// The dotted path of the host field for jsonl input.
+    pub field: String,
+    /// The header name or index of the host column for csv input.
+    pub column: String,
+    /// Whether csv input starts with a header row.
+    pub header: bool,
+    /// Whether the scanned addresses in masscan input are kept.
+    pub ips: bool,
+    /// Whether the data sources in amass input are carried through.
+    pub sources: bool,
+    /// Whether the mailboxes of email input are kept.
+    pub mailbox: bool,
+    /// Whether `#` starts a comment in text input.
+    pub comments: bool,
+}
+
+/// How each input line is turned into hosts.
+#[derive(Clone, Debug)]
+pub enum InputFormat {
+    /// One host per line, skipping blank lines and, unless `#` is part of
+    /// the hosts, comments.
+    Text { comments: bool },
+    /// One JSON object per line, the host is read from the given field path.
+    Jsonl { path: Vec<String> },
+    /// Comma separated values, the host is read from the given column.
+    Csv(CsvState),
+    /// An Nmap XML report.
+    Nmap,
+    /// masscan list or JSON output, optionally keeping the scanned addresses.
+    Masscan { ips: bool },
+    /// amass JSON output, optionally carrying the data sources through.
+    Amass { sources: bool },
+    /// A Burp Suite XML export of sitemap or proxy history items.
+    Burp,
+    /// massdns simple output, one resource record per line.
+    Massdns,
+    /// A BIND style zone file.
+    Zone(ZoneState),
+    /// An /etc/hosts style file, an address followed by its hostnames.
+    Hosts,
+    /// X.509 certificates, PEM encoded or DER.
+    Certs,
+    /// Email addresses, optionally keeping the mailbox.
+    Email { mailbox: bool },
+    /// An html page, hosts are read from the links in it.
+    Html,
+    /// Any text, hosts are whatever looks like a hostname in it.
+    Grep,
+    /// A javascript file, hosts are read from its string literals.
+    Js,
+    /// A sitemap, sitemap index or robots.txt, hosts are read from the urls
+    /// in it.
+    Sitemap,
+}
+
+impl InputFormat {
+    /// Builds the input format from the `--input-format` and related arguments.
+    pub fn new(name: &str, options: &FormatOptions) -> InputFormat {
+        match name {
+            "jsonl" => InputFormat::Jsonl {
+                path: options.field.split('.').map(|s| s.to_string()).collect(),
+            },
+            "csv" => InputFormat::Csv(CsvState::new(&options.column, options.header)),
+            "nmap" => InputFormat::Nmap,
+            "masscan" => InputFormat::Masscan { ips: options.ips },
+            "amass" => InputFormat::Amass {
+                sources: options.sources,
+            },
+            "burp" => InputFormat::Burp,
+            "massdns" => InputFormat::Massdns,
+            "zone" => InputFormat::Zone(ZoneState::default()),
+            "hosts" => InputFormat::Hosts,
+            "pem" | "der" => InputFormat::Certs,
+            "html" => InputFormat::Html,
+            "grep" => InputFormat::Grep,
+            "js" => InputFormat::Js,
+            "sitemap" | "robots" => InputFormat::Sitemap,
+            "email" => InputFormat::Email {
+                mailbox: options.mailbox,
+            },
+            _ => InputFormat::Text {
+                comments: options.comments,
+            },
+        }
+    }
+
+    /// Returns the hosts found on a single line of input.
+    pub fn hosts(&mut self, line: &str) -> Vec<Host> {
+        let hosts = match self {
+            InputFormat::Text { comments } => text::host(line, *comments).into_iter().collect(),
+            InputFormat::Jsonl { path } => jsonl::hosts(line, path),
+            InputFormat::Csv(state) => state.hosts(line),
+            InputFormat::Nmap
+            | InputFormat::Burp
+            | InputFormat::Certs
+            | InputFormat::Html
+            | InputFormat::Js
+            | InputFormat::Sitemap => vec![],
+            InputFormat::Masscan { ips } => masscan::hosts(line, *ips),
+            InputFormat::Massdns => massdns::hosts(line),
+            InputFormat::Zone(state) => state.hosts(line),
+            InputFormat::Hosts => hostsfile::hosts(line),
+            InputFormat::Amass { sources } => return amass::hosts(line, *sources),
+            InputFormat::Email { mailbox } => return email::hosts(line, *mailbox),
+            InputFormat::Grep => {
+                let mut hosts = text::hostnames(line);
+                hosts.dedup();
+                hosts
+            }
+        };
+        hosts.into_iter().map(Host::from).collect()
+    }
+
+    /// Whether the input has to be read as a whole document rather than
+    /// line by line.
+    pub fn is_document(&self) -> bool {
+        matches!(
+            self,
+            InputFormat::Nmap
+                | InputFormat::Burp
+                | InputFormat::Certs
+                | InputFormat::Html
+                | InputFormat::Js
+                | InputFormat::Sitemap
+        )
+    }
+
+    /// Whether the input is binary and must be read without transcoding it.
+    pub fn is_binary(&self) -> bool {
+        matches!(self, InputFormat::Certs)
+    }
+
+    /// Returns the urls of the documents a whole document points at that
+    /// have to be read as well, such as the sitemaps of a sitemap index.
+    pub fn linked_documents(&self, data: &[u8]) -> Vec<String> {
+        match self {
+            InputFormat::Sitemap => sitemap::sitemaps(&String::from_utf8_lossy(data)),
+            _ => vec![],
+        }
+    }
+
+    /// Returns the hosts found in a whole document.
+    pub fn document_hosts(&mut self, data: &[u8]) -> Vec<Host> {
+        let hosts = match self {
+            InputFormat::Certs => certs::hosts(data),
+            InputFormat::Nmap => nmap::hosts(&String::from_utf8_lossy(data)),
+            InputFormat::Burp => burp::hosts(&String::from_utf8_lossy(data)),
+            InputFormat::Html => html::hosts(&String::from_utf8_lossy(data)),
+            InputFormat::Js => js::hosts(&String::from_utf8_lossy(data)),
+            InputFormat::Sitemap => sitemap::hosts(&String::from_utf8_lossy(data)),
+            _ => {
+                return String::from_utf8_lossy(data)
+                    .lines()
+                    .flat_map(|line| self.hosts(line))
+                    .collect()
+            }
+        };
+        hosts.into_iter().map(Host::from).collect()
+    }
+}
@@ -0,0 +1,27 @@
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+/// Returns every hostname recorded in an Nmap XML report, both user supplied
+/// names and PTR records.
+pub fn hosts(text: &str) -> Vec<String> {
+    let mut reader = Reader::from_str(text);
+    let mut hosts = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) if e.name().as_ref() == b"hostname" => {
+                if let Ok(Some(name)) = e.try_get_attribute("name") {
+                    if let Ok(name) = name.unescape_value() {
+                        hosts.push(name.to_string());
+                    }
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                warn!("error parsing nmap xml: {}", e);
+                break;
+            }
+            _ => continue,
+        }
+    }
+    hosts
+}
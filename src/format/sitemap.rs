@@ -0,0 +1,78 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Matches the urls of a sitemap: the `loc` of pages, images, videos and
+/// nested sitemaps, plain or in CDATA, and the `href` of alternate links.
+const LOCATION_PATTERN: &str = r#"(?i)<(?:[a-z]+:)?loc>\s*(?:<!\[CDATA\[)?\s*([^<\]\s]+)|<(?:[a-z]+:)?link\b[^>]*\bhref\s*=\s*["']([^"']+)["']"#;
+
+fn location_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(LOCATION_PATTERN).unwrap())
+}
+
+/// Returns the hosts of the urls in a sitemap or robots.txt file.
+pub fn hosts(text: &str) -> Vec<String> {
+    let mut hosts: Vec<String> = Vec::new();
+    for host in urls(text).iter().filter_map(|url| url_host(url)) {
+        if !hosts.contains(&host) {
+            hosts.push(host);
+        }
+    }
+    if !is_xml(text) {
+        // yandex's host directive names the main mirror without a scheme
+        for (_, value) in directives(text).filter(|(key, _)| key.eq_ignore_ascii_case("host")) {
+            let host = value.split(['/', ':']).next().unwrap_or_default();
+            if !host.is_empty() && !hosts.iter().any(|h| h == host) {
+                hosts.push(host.to_string());
+            }
+        }
+    }
+    hosts
+}
+
+/// Returns the sitemaps a file points at and that have to be fetched in
+/// turn: the `Sitemap` lines of a robots.txt and the entries of a sitemap
+/// index.
+pub fn sitemaps(text: &str) -> Vec<String> {
+    if !is_xml(text) || text.contains("<sitemapindex") {
+        urls(text)
+    } else {
+        vec![]
+    }
+}
+
+/// The absolute urls of a sitemap, or of the `Sitemap` lines of a robots.txt.
+fn urls(text: &str) -> Vec<String> {
+    if is_xml(text) {
+        return location_regex()
+            .captures_iter(text)
+            .filter_map(|captures| captures.get(1).or(captures.get(2)))
+            .map(|url| url.as_str().replace("&amp;", "&"))
+            .collect();
+    }
+    directives(text)
+        .filter(|(key, _)| key.eq_ignore_ascii_case("sitemap"))
+        .map(|(_, value)| value.to_string())
+        .collect()
+}
+
+/// The `key: value` lines of a robots.txt, without comments.
+fn directives(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    text.lines().filter_map(|line| {
+        let line = line.split('#').next().unwrap_or_default();
+        let (key, value) = line.split_once(':')?;
+        let value = value.trim();
+        (!value.is_empty()).then(|| (key.trim(), value))
+    })
+}
+
+fn is_xml(text: &str) -> bool {
+    text.trim_start_matches('\u{feff}')
+        .trim_start()
+        .starts_with('<')
+}
+
+fn url_host(url: &str) -> Option<String> {
+    let url = url::Url::parse(url.trim()).ok()?;
+    url.host_str().map(|host| host.to_string())
+}
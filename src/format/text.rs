@@ -0,0 +1,31 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Matches dotted names whose last label is alphabetic, so version numbers
+/// and ip addresses are not mistaken for hosts.
+const HOSTNAME_PATTERN: &str =
+    r"(?i)\b(?:[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?\.)+[a-z](?:[a-z0-9-]{0,61}[a-z0-9])?\b";
+
+fn hostname_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(HOSTNAME_PATTERN).unwrap())
+}
+
+/// Returns the hostname-looking tokens found anywhere in free text.
+pub fn hostnames(text: &str) -> Vec<String> {
+    hostname_regex()
+        .find_iter(text)
+        .map(|m| m.as_str().to_string())
+        .collect()
+}
+
+/// Returns the host on a line of a host list, if it is not blank or, with
+/// `comments`, only a comment.
+pub fn host(line: &str, comments: bool) -> Option<String> {
+    let line = match line.find('#') {
+        Some(start) if comments => &line[..start],
+        _ => line,
+    };
+    let line = line.trim();
+    (!line.is_empty()).then(|| line.to_string())
+}
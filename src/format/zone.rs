@@ -0,0 +1,138 @@
+/// Resource record types whose data is a single hostname, with the position
+/// of that name among the record's data fields.
+const NAME_RECORDS: [(&str, usize); 6] = [
+    ("CNAME", 0),
+    ("DNAME", 0),
+    ("NS", 0),
+    ("PTR", 0),
+    ("MX", 1),
+    ("SRV", 3),
+];
+
+const CLASSES: [&str; 4] = ["IN", "CH", "HS", "CS"];
+
+/// Per source zone file state: the current `$ORIGIN`, the owner that blank
+/// owner fields repeat, and any record still open inside parentheses.
+#[derive(Clone, Debug, Default)]
+pub struct ZoneState {
+    origin: Option<String>,
+    owner: Option<String>,
+    pending: String,
+    depth: usize,
+}
+
+impl ZoneState {
+    /// Returns the fully qualified names of the record ending on this line:
+    /// its owner, when it differs from the previous record's, and the target
+    /// of records that point at another host.
+    pub fn hosts(&mut self, line: &str) -> Vec<String> {
+        let line = strip_comment(line);
+        if self.depth == 0 {
+            self.pending.clear();
+        }
+        let (open, close) = parentheses(line);
+        self.depth = (self.depth + open).saturating_sub(close);
+
+        // a record with an open parenthesis carries on onto the next lines
+        if self.pending.is_empty() {
+            self.pending.push_str(line);
+        } else {
+            self.pending.push(' ');
+            self.pending.push_str(line.trim());
+        }
+        if self.depth > 0 {
+            return vec![];
+        }
+
+        let record = std::mem::take(&mut self.pending).replace(['(', ')'], " ");
+        let mut fields = record.split_whitespace();
+
+        if record.starts_with('$') {
+            match fields.next() {
+                Some(directive) if directive.eq_ignore_ascii_case("$ORIGIN") => {
+                    if let Some(origin) = fields.next() {
+                        self.origin = Some(self.qualify(origin));
+                    }
+                }
+                _ => {}
+            }
+            return vec![];
+        }
+
+        let mut hosts = vec![];
+
+        // a record starting with whitespace belongs to the previous owner
+        if !record.starts_with(char::is_whitespace) {
+            let owner = match fields.next() {
+                Some(owner) => self.qualify(owner),
+                None => return vec![],
+            };
+            if self.owner.as_ref() != Some(&owner) {
+                hosts.push(owner.clone());
+            }
+            self.owner = Some(owner);
+        }
+
+        // the ttl and class are optional and may come in either order
+        let kind = fields.find(|field| {
+            !field.starts_with(|c: char| c.is_ascii_digit())
+                && !CLASSES
+                    .iter()
+                    .any(|class| field.eq_ignore_ascii_case(class))
+        });
+        let position = kind.and_then(|kind| {
+            NAME_RECORDS
+                .iter()
+                .find(|(name, _)| kind.eq_ignore_ascii_case(name))
+                .map(|(_, position)| *position)
+        });
+        if let Some(target) = position.and_then(|position| fields.nth(position)) {
+            hosts.push(self.qualify(target));
+        }
+
+        hosts.retain(|host| !host.is_empty());
+        hosts
+    }
+
+    /// Resolves a name against the current origin, without the trailing dot.
+    fn qualify(&self, name: &str) -> String {
+        if name == "@" {
+            return self.origin.clone().unwrap_or_default();
+        }
+        if let Some(name) = name.strip_suffix('.') {
+            return name.to_string();
+        }
+        match &self.origin {
+            Some(origin) if !origin.is_empty() => format!("{}.{}", name, origin),
+            _ => name.to_string(),
+        }
+    }
+}
+
+/// Cuts a line off at the first `;` that is not inside a quoted string.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Counts the opening and closing parentheses outside quoted strings.
+fn parentheses(line: &str) -> (usize, usize) {
+    let mut quoted = false;
+    let (mut open, mut close) = (0, 0);
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '(' if !quoted => open += 1,
+            ')' if !quoted => close += 1,
+            _ => {}
+        }
+    }
+    (open, close)
+}
@@ -0,0 +1,98 @@
+use async_std::channel::{self, Sender};
+use futures::StreamExt;
+use serde_json::Value;
+use std::error::Error;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use super::{channel_reader, Reader};
+
+/// How many times in a row reconnecting may fail before giving up.
+const MAX_RETRIES: u32 = 5;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Subscribes to a CertStream server and yields every domain of each new
+/// certificate until the process is stopped, reconnecting whenever the
+/// server drops the connection.
+pub async fn open(url: &str) -> Result<Reader, Box<dyn Error + Send + Sync + 'static>> {
+    // connect before handing out the reader so failures surface as open errors
+    let (socket, _) = tokio_tungstenite::connect_async(url).await?;
+    let (tx, rx) = channel::bounded(1024);
+    let url = url.to_string();
+
+    tokio::spawn(async move {
+        let mut socket = socket;
+        let mut attempts = 0;
+        loop {
+            if read(socket, &tx).await.is_err() {
+                // the parser has stopped reading
+                return;
+            }
+            loop {
+                attempts += 1;
+                if attempts > MAX_RETRIES {
+                    warn!(
+                        "error reading {}: giving up after {} retries",
+                        url, MAX_RETRIES
+                    );
+                    return;
+                }
+                warn!(
+                    "error reading {}: connection closed, retrying ({}/{})",
+                    url, attempts, MAX_RETRIES
+                );
+                tokio::time::sleep(Duration::from_secs(1 << (attempts - 1))).await;
+                if let Ok((reconnected, _)) = tokio_tungstenite::connect_async(&url).await {
+                    socket = reconnected;
+                    attempts = 0;
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok(channel_reader(rx))
+}
+
+/// Forwards the domains from the socket until it closes. Fails only when
+/// nothing is reading the hosts any more.
+async fn read(mut socket: Socket, tx: &Sender<String>) -> Result<(), ()> {
+    while let Some(Ok(message)) = socket.next().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        for domain in domains(&text) {
+            if tx.send(domain).await.is_err() {
+                return Err(());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the domains of a certificate update, either the full message of
+/// the `certificate_update` type or the `dns_entries` of domains-only feeds.
+fn domains(text: &str) -> Vec<String> {
+    let message: Value = match serde_json::from_str(text) {
+        Ok(message) => message,
+        Err(_) => return vec![],
+    };
+    let domains = match message["message_type"].as_str() {
+        Some("certificate_update") => &message["data"]["leaf_cert"]["all_domains"],
+        Some("dns_entries") => &message["data"],
+        _ => return vec![],
+    };
+    match domains.as_array() {
+        Some(domains) => domains
+            .iter()
+            .filter_map(|domain| domain.as_str())
+            .map(|domain| domain.to_string())
+            .collect(),
+        None => vec![],
+    }
+}
@@ -0,0 +1,111 @@
+use async_std::io;
+use bytes::Bytes;
+use encoding_rs::{Decoder, Encoding, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use futures::{stream, AsyncBufReadExt, AsyncReadExt, TryStreamExt};
+use std::borrow::Cow;
+
+use super::Reader;
+
+/// Size of the chunks read from the source before they are decoded.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// How the bytes of an input are turned into text.
+#[derive(Clone, Copy, Debug)]
+pub enum InputEncoding {
+    /// Follow a byte order mark or the shape of utf-16 text, otherwise read
+    /// utf-8 and fall back to latin-1 for the lines that are not.
+    Auto,
+    Fixed(&'static Encoding),
+}
+
+impl InputEncoding {
+    /// Looks up an encoding by any of its WHATWG labels, or `auto`.
+    pub fn new(label: &str) -> Result<InputEncoding, String> {
+        if label.eq_ignore_ascii_case("auto") {
+            return Ok(InputEncoding::Auto);
+        }
+        match Encoding::for_label(label.as_bytes()) {
+            Some(encoding) => Ok(InputEncoding::Fixed(encoding)),
+            None => Err(format!("unknown encoding {}", label)),
+        }
+    }
+
+    /// Returns the encoding that `head`, the start of an input, has to be
+    /// decoded from, or `None` when its lines can be read as they are.
+    pub fn detect(&self, head: &[u8]) -> Option<&'static Encoding> {
+        match self {
+            InputEncoding::Fixed(encoding) => Some(encoding),
+            InputEncoding::Auto => match Encoding::for_bom(head) {
+                Some((encoding, _)) => Some(encoding),
+                None => utf16(head),
+            },
+        }
+    }
+}
+
+/// Guesses utf-16 without a byte order mark from the zero bytes that ascii
+/// text leaves in every other position.
+fn utf16(head: &[u8]) -> Option<&'static Encoding> {
+    let pairs = head.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let even = head.iter().step_by(2).filter(|b| **b == 0).count();
+    let odd = head.iter().skip(1).step_by(2).filter(|b| **b == 0).count();
+    if odd > pairs / 2 && even == 0 {
+        Some(UTF_16LE)
+    } else if even > pairs / 2 && odd == 0 {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Wraps the reader so it yields utf-8, transcoding from whatever the start
+/// of the input says it is.
+pub async fn transcode(mut reader: Reader, encoding: InputEncoding) -> io::Result<Reader> {
+    let head = reader.fill_buf().await?;
+    let encoding = match encoding.detect(head) {
+        Some(encoding) => encoding,
+        None => return Ok(reader),
+    };
+
+    let body = stream::unfold(Some((reader, encoding.new_decoder())), |state| async move {
+        let (mut reader, mut decoder) = state?;
+        match next_chunk(&mut reader, &mut decoder).await {
+            Ok(Some(chunk)) => Some((Ok(chunk), Some((reader, decoder)))),
+            Ok(None) => None,
+            Err(e) => Some((Err(e), None)),
+        }
+    });
+    Ok(Box::new(io::BufReader::new(
+        Box::pin(body).into_async_read(),
+    )))
+}
+
+/// Decodes the next non-empty piece of the input, or `None` at the end.
+async fn next_chunk(reader: &mut Reader, decoder: &mut Decoder) -> io::Result<Option<Bytes>> {
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        let last = n == 0;
+        let mut text =
+            String::with_capacity(decoder.max_utf8_buffer_length(n).unwrap_or(n * 3 + 16));
+        let _ = decoder.decode_to_string(&buf[..n], &mut text, last);
+        if !text.is_empty() {
+            return Ok(Some(Bytes::from(text)));
+        }
+        if last {
+            return Ok(None);
+        }
+    }
+}
+
+/// Reads a line as utf-8, or as latin-1 when it is not valid utf-8, so no
+/// line of a dirty list is dropped.
+pub fn decode_line(line: &[u8]) -> Cow<'_, str> {
+    match std::str::from_utf8(line) {
+        Ok(line) => Cow::Borrowed(line),
+        Err(_) => WINDOWS_1252.decode_without_bom_handling(line).0,
+    }
+}
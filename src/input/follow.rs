@@ -0,0 +1,95 @@
+use async_std::channel;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use super::{channel_reader, encoding, Reader};
+
+/// How long to wait for more data once the end of a file is reached.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Reads the files from the start and then keeps waiting for new lines, like
+/// `tail -f`. Lines from all files are interleaved as they arrive. A file
+/// that is truncated is read again from the start, and one that is replaced,
+/// for instance by log rotation, is reopened.
+pub fn open(paths: &[PathBuf]) -> Reader {
+    let (tx, rx) = channel::bounded(1024);
+    for path in paths {
+        let path = path.clone();
+        let tx = tx.clone();
+        thread::spawn(move || follow(&path, tx));
+    }
+    channel_reader(rx)
+}
+
+fn follow(path: &Path, tx: channel::Sender<String>) {
+    let mut reader = match File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => {
+            warn!("could not open file {}: {}", path.display(), e);
+            return;
+        }
+    };
+    let mut identity = identity_of(path);
+    let mut position = 0;
+    let mut line = Vec::new();
+
+    loop {
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => {
+                thread::sleep(POLL_INTERVAL);
+
+                let current = identity_of(path);
+                if current.is_some() && current != identity {
+                    // the file was replaced, start over on the new one
+                    if let Ok(file) = File::open(path) {
+                        reader = BufReader::new(file);
+                        identity = current;
+                        position = 0;
+                        line.clear();
+                    }
+                } else if std::fs::metadata(path).map(|m| m.len()).unwrap_or(0) < position {
+                    // the file was truncated, read it again from the start
+                    if reader.seek(SeekFrom::Start(0)).is_ok() {
+                        position = 0;
+                        line.clear();
+                    }
+                }
+            }
+            Ok(n) => {
+                position += n as u64;
+                // wait for the rest of a line that is still being written
+                if !line.ends_with(b"\n") {
+                    continue;
+                }
+                // a line that is not utf-8 is read like every other input's
+                let host = encoding::decode_line(&line)
+                    .trim_end_matches(['\r', '\n'])
+                    .to_string();
+                line.clear();
+                if tx.send_blocking(host).is_err() {
+                    return;
+                }
+            }
+            Err(e) => {
+                warn!("error reading {}: {}", path.display(), e);
+                return;
+            }
+        }
+    }
+}
+
+/// Identifies the file currently at `path`, so a replaced file can be told
+/// apart from the one that is open.
+#[cfg(unix)]
+fn identity_of(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|m| (m.dev(), m.ino()))
+}
+
+#[cfg(not(unix))]
+fn identity_of(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
@@ -0,0 +1,149 @@
+use async_std::io;
+use bytes::Bytes;
+use futures::{stream, TryStreamExt};
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Client, Response, StatusCode};
+use std::error::Error;
+use std::time::Duration;
+
+use super::Reader;
+
+/// How many times a failed request is retried before giving up.
+const MAX_RETRIES: u32 = 5;
+
+/// Produces the extra headers for each request, e.g. a fresh signature.
+pub type Sign = Box<dyn Fn() -> HeaderMap + Send + Sync>;
+
+/// State of an in-progress download, kept across reconnects.
+struct Download {
+    client: Client,
+    url: String,
+    sign: Option<Sign>,
+    response: Option<Response>,
+    offset: u64,
+    skip: u64,
+    attempts: u32,
+}
+
+impl Download {
+    /// Connects, resuming from the current offset when some of the body has
+    /// already been read. Servers that ignore the range header get the
+    /// already-read prefix skipped instead.
+    async fn connect(&mut self) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+        let mut request = self.client.get(&self.url);
+        if let Some(sign) = &self.sign {
+            request = request.headers(sign());
+        }
+        if self.offset > 0 {
+            request = request.header(header::RANGE, format!("bytes={}-", self.offset));
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            // client errors will not go away by asking again
+            if status.is_client_error() && status != StatusCode::TOO_MANY_REQUESTS {
+                self.attempts = MAX_RETRIES;
+            }
+            return Err(format!("server returned {}", status).into());
+        }
+
+        self.skip = if self.offset > 0 && status != StatusCode::PARTIAL_CONTENT {
+            self.offset
+        } else {
+            0
+        };
+        self.response = Some(response);
+        Ok(())
+    }
+
+    /// Returns the next chunk of the body, reconnecting on failure.
+    async fn next_chunk(&mut self) -> Option<io::Result<Bytes>> {
+        loop {
+            if self.attempts > MAX_RETRIES {
+                return None;
+            }
+
+            if self.response.is_none() {
+                if let Err(e) = self.connect().await {
+                    if let Err(e) = self.backoff(e).await {
+                        return Some(Err(e));
+                    }
+                    continue;
+                }
+            }
+
+            let response = self.response.as_mut()?;
+            match response.chunk().await {
+                Ok(Some(mut chunk)) => {
+                    self.attempts = 0;
+                    if self.skip > 0 {
+                        let n = self.skip.min(chunk.len() as u64);
+                        self.skip -= n;
+                        chunk = chunk.slice(n as usize..);
+                    }
+                    if chunk.is_empty() {
+                        continue;
+                    }
+                    self.offset += chunk.len() as u64;
+                    return Some(Ok(chunk));
+                }
+                Ok(None) => return None,
+                Err(e) => {
+                    self.response = None;
+                    if let Err(e) = self.backoff(e.into()).await {
+                        return Some(Err(e));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Waits before the next attempt, or fails once the retries are used up.
+    async fn backoff(&mut self, e: Box<dyn Error + Send + Sync + 'static>) -> io::Result<()> {
+        self.attempts += 1;
+        if self.attempts > MAX_RETRIES {
+            return Err(io::Error::other(e.to_string()));
+        }
+        warn!(
+            "error reading {}: {}, retrying ({}/{})",
+            self.url, e, self.attempts, MAX_RETRIES
+        );
+        tokio::time::sleep(Duration::from_secs(1 << (self.attempts - 1))).await;
+        Ok(())
+    }
+}
+
+/// Streams the body of a remote host list.
+pub async fn open(url: &str) -> Result<Reader, Box<dyn Error + Send + Sync + 'static>> {
+    open_signed(url, None).await
+}
+
+/// Streams the body of a remote host list, adding the headers from `sign`
+/// to every request.
+pub async fn open_signed(
+    url: &str,
+    sign: Option<Sign>,
+) -> Result<Reader, Box<dyn Error + Send + Sync + 'static>> {
+    let mut download = Download {
+        client: Client::new(),
+        url: url.to_string(),
+        sign,
+        response: None,
+        offset: 0,
+        skip: 0,
+        attempts: 0,
+    };
+
+    // connect before handing out the reader so failures surface as open errors
+    while let Err(e) = download.connect().await {
+        download.backoff(e).await?;
+    }
+
+    let body = stream::unfold(download, |mut download| async move {
+        download.next_chunk().await.map(|chunk| (chunk, download))
+    });
+    Ok(Box::new(io::BufReader::new(
+        Box::pin(body).into_async_read(),
+    )))
+}
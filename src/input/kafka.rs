@@ -0,0 +1,68 @@
+use ::kafka::consumer::{Consumer, FetchOffset, GroupOffsetStorage};
+use async_std::channel;
+use futures::channel::oneshot;
+use std::error::Error;
+use std::time::Duration;
+
+use super::{channel_reader, Reader};
+
+/// Consumes host strings from a Kafka topic until the process is stopped.
+/// Offsets are committed to the consumer group after every poll so a
+/// restarted consumer picks up where the last one stopped.
+pub async fn open(
+    brokers: &str,
+    topic: &str,
+    group: &str,
+) -> Result<Reader, Box<dyn Error + Send + Sync + 'static>> {
+    let (ready_tx, ready_rx) = oneshot::channel();
+    let (tx, rx) = channel::bounded(1024);
+    let brokers = brokers.split(',').map(|s| s.trim().to_string()).collect();
+    let topic = topic.to_string();
+    let group = group.to_string();
+
+    std::thread::spawn(move || {
+        let mut consumer = match Consumer::from_hosts(brokers)
+            .with_topic(topic)
+            .with_group(group)
+            .with_fallback_offset(FetchOffset::Earliest)
+            .with_offset_storage(Some(GroupOffsetStorage::Kafka))
+            .create()
+        {
+            Ok(consumer) => consumer,
+            Err(e) => {
+                let _ = ready_tx.send(Err(e.to_string()));
+                return;
+            }
+        };
+        let _ = ready_tx.send(Ok(()));
+
+        loop {
+            let sets = match consumer.poll() {
+                Ok(sets) => sets,
+                Err(e) => {
+                    warn!("error polling kafka: {}", e);
+                    std::thread::sleep(Duration::from_secs(1));
+                    continue;
+                }
+            };
+            for set in sets.iter() {
+                for message in set.messages() {
+                    let host = String::from_utf8_lossy(message.value).into_owned();
+                    if tx.send_blocking(host).is_err() {
+                        return;
+                    }
+                }
+                let _ = consumer.consume_messageset(set);
+            }
+            if let Err(e) = consumer.commit_consumed() {
+                warn!("error committing kafka offsets: {}", e);
+            }
+        }
+    });
+
+    match ready_rx.await {
+        Ok(Ok(())) => Ok(channel_reader(rx)),
+        Ok(Err(e)) => Err(e.into()),
+        Err(_) => Err("consumer thread exited".into()),
+    }
+}
@@ -0,0 +1,311 @@
+use async_compression::futures::bufread::{GzipDecoder, ZstdDecoder};
+use async_std::fs::File;
+use async_std::io;
+use async_std::io::BufRead;
+use futures::{AsyncBufReadExt, StreamExt, TryStreamExt};
+use memmap2::Mmap;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// A line oriented stream of hosts.
+pub type Reader = Box<dyn BufRead + Unpin + Send>;
+
+mod certstream;
+pub mod encoding;
+mod follow;
+mod http;
+#[cfg(feature = "kafka")]
+mod kafka;
+mod pcap;
+mod redis;
+mod s3;
+mod sqlite;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Somewhere to read hosts from.
+#[derive(Clone, Debug)]
+pub enum Source {
+    Stdin,
+    Hosts(Vec<String>),
+    File(PathBuf),
+    Follow(Vec<PathBuf>),
+    Url(String),
+    S3(String),
+    Pcap(PathBuf),
+    Sqlite {
+        path: PathBuf,
+        query: String,
+    },
+    Redis {
+        url: String,
+        key: String,
+        group: String,
+    },
+    #[cfg(feature = "kafka")]
+    Kafka {
+        brokers: String,
+        topic: String,
+        group: String,
+    },
+    CertStream(String),
+}
+
+impl Source {
+    /// Opens the source for reading, transparently decompressing gzip and
+    /// zstd streams.
+    pub async fn open(&self) -> Result<Reader, Box<dyn Error + Send + Sync + 'static>> {
+        let reader = self.open_raw().await?;
+        match decompress(reader).await {
+            Ok(reader) => Ok(reader),
+            Err(e) => Err(format!("could not read {}: {}", self, e).into()),
+        }
+    }
+
+    async fn open_raw(&self) -> Result<Reader, Box<dyn Error + Send + Sync + 'static>> {
+        match self {
+            Source::Stdin => Ok(Box::new(io::BufReader::new(io::stdin()))),
+            Source::Hosts(hosts) => Ok(Box::new(io::Cursor::new(hosts.join("\n").into_bytes()))),
+            Source::File(path) => match std::fs::File::open(path) {
+                Ok(file) => Ok(Box::new(io::BufReader::new(File::from(file)))),
+                Err(e) => Err(format!("could not open file {}: {}", path.display(), e).into()),
+            },
+            Source::Follow(paths) => Ok(follow::open(paths)),
+            Source::Url(url) => match http::open(url).await {
+                Ok(reader) => Ok(reader),
+                Err(e) => Err(format!("could not fetch {}: {}", url, e).into()),
+            },
+            Source::S3(location) => match s3::open(location).await {
+                Ok(reader) => Ok(reader),
+                Err(e) => Err(format!("could not fetch {}: {}", location, e).into()),
+            },
+            Source::Pcap(path) => {
+                let capture = path.clone();
+                match async_std::task::spawn_blocking(move || pcap::hosts(&capture)).await {
+                    Ok(hosts) => Ok(Box::new(io::Cursor::new(hosts.join("\n").into_bytes()))),
+                    Err(e) => {
+                        Err(format!("could not read capture {}: {}", path.display(), e).into())
+                    }
+                }
+            }
+            Source::Sqlite { path, query } => match sqlite::open(path, query).await {
+                Ok(reader) => Ok(reader),
+                Err(e) => Err(format!("could not query {}: {}", path.display(), e).into()),
+            },
+            Source::Redis { url, key, group } => match redis::open(url, key, group).await {
+                Ok(reader) => Ok(reader),
+                Err(e) => Err(format!("could not read {} from {}: {}", key, url, e).into()),
+            },
+            #[cfg(feature = "kafka")]
+            Source::Kafka {
+                brokers,
+                topic,
+                group,
+            } => match kafka::open(brokers, topic, group).await {
+                Ok(reader) => Ok(reader),
+                Err(e) => Err(format!("could not consume {}: {}", topic, e).into()),
+            },
+            Source::CertStream(url) => match certstream::open(url).await {
+                Ok(reader) => Ok(reader),
+                Err(e) => Err(format!("could not connect to {}: {}", url, e).into()),
+            },
+        }
+    }
+
+    /// The size of a plain file, which is how far reading it gets. Nothing
+    /// else has a size known up front.
+    pub fn size(&self) -> Option<u64> {
+        let path = match self {
+            Source::File(path) => path,
+            _ => return None,
+        };
+        let mut file = std::fs::File::open(path).ok()?;
+        // peeking into a pipe would take its first bytes away
+        if !file.metadata().ok()?.is_file() {
+            return None;
+        }
+        let mut magic = [0; 4];
+        let n = std::io::Read::read(&mut file, &mut magic).ok()?;
+        if magic[..n].starts_with(&GZIP_MAGIC) || magic[..n] == ZSTD_MAGIC {
+            return None;
+        }
+        file.metadata().ok().map(|metadata| metadata.len())
+    }
+
+    /// Maps a plain file into memory so its lines can be parsed in place,
+    /// without copying them out first. Compressed files and anything that
+    /// can not be mapped are left to `open`.
+    pub fn map(&self) -> Option<Mmap> {
+        let path = match self {
+            Source::File(path) => path,
+            _ => return None,
+        };
+        let file = std::fs::File::open(path).ok()?;
+        // the file must not be truncated while it is mapped, the same
+        // assumption every other reader of a static host list makes
+        let map = unsafe { Mmap::map(&file) }.ok()?;
+        if map.starts_with(&GZIP_MAGIC) || map.starts_with(&ZSTD_MAGIC) {
+            return None;
+        }
+        Some(map)
+    }
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Stdin => write!(f, "stdin"),
+            Source::Hosts(_) => write!(f, "arguments"),
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Follow(paths) => {
+                let paths: Vec<_> = paths
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                write!(f, "{}", paths.join(", "))
+            }
+            Source::Url(url) => write!(f, "{}", url),
+            Source::S3(location) => write!(f, "{}", location),
+            Source::Pcap(path) => write!(f, "{}", path.display()),
+            Source::Sqlite { path, .. } => write!(f, "{}", path.display()),
+            Source::Redis { key, .. } => write!(f, "redis key {}", key),
+            #[cfg(feature = "kafka")]
+            Source::Kafka { topic, .. } => write!(f, "kafka topic {}", topic),
+            Source::CertStream(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+/// Turns a channel of hosts pushed by another thread or task into a reader,
+/// one host per line.
+fn channel_reader(rx: async_std::channel::Receiver<String>) -> Reader {
+    fallible_reader(rx.map(Ok))
+}
+
+/// Like `channel_reader`, for sources that can fail part way through: an
+/// error ends the input and is reported by the reader like a failed read.
+fn fallible_reader(
+    hosts: impl futures::Stream<Item = io::Result<String>> + Send + 'static,
+) -> Reader {
+    let lines = hosts.map_ok(|mut host| {
+        host.push('\n');
+        host.into_bytes()
+    });
+    Box::new(io::BufReader::new(Box::pin(lines).into_async_read()))
+}
+
+/// Wraps the reader in a decoder when the stream starts with a gzip or zstd
+/// magic number, otherwise the reader is returned untouched.
+async fn decompress(mut reader: Reader) -> io::Result<Reader> {
+    let head = reader.fill_buf().await?;
+    if head.starts_with(&GZIP_MAGIC) {
+        let mut decoder = GzipDecoder::new(reader);
+        decoder.multiple_members(true);
+        Ok(Box::new(io::BufReader::new(decoder)))
+    } else if head.starts_with(&ZSTD_MAGIC) {
+        let mut decoder = ZstdDecoder::new(reader);
+        decoder.multiple_members(true);
+        Ok(Box::new(io::BufReader::new(decoder)))
+    } else {
+        Ok(reader)
+    }
+}
+
+/// Expands the given paths into file sources, treating any containing glob
+/// characters as patterns. Every resulting file is checked up front so a typo
+/// is reported before any work starts.
+pub fn file_sources(
+    patterns: &[&str],
+) -> Result<Vec<Source>, Box<dyn Error + Send + Sync + 'static>> {
+    let mut sources = Vec::new();
+    for pattern in patterns {
+        if !pattern.contains(['*', '?', '[']) {
+            let path = PathBuf::from(pattern);
+            if let Err(e) = std::fs::File::open(&path) {
+                return Err(format!("could not open file {}: {}", pattern, e).into());
+            }
+            sources.push(Source::File(path));
+            continue;
+        }
+
+        let entries = match glob::glob(pattern) {
+            Ok(entries) => entries,
+            Err(e) => return Err(format!("invalid glob pattern {}: {}", pattern, e).into()),
+        };
+
+        let before = sources.len();
+        for entry in entries {
+            match entry {
+                Ok(path) if path.is_file() => sources.push(Source::File(path)),
+                Ok(_) => continue,
+                Err(e) => return Err(format!("could not read {}: {}", pattern, e).into()),
+            }
+        }
+
+        if sources.len() == before {
+            return Err(format!("no files matched {}", pattern).into());
+        }
+    }
+    Ok(sources)
+}
+
+/// Extensions of the files picked up when walking a directory.
+const DIR_EXTENSIONS: [&str; 2] = ["txt", "lst"];
+
+/// Walks the given directories recursively and returns a file source for
+/// every host list found, in a stable order.
+pub fn dir_sources(dirs: &[&str]) -> Result<Vec<Source>, Box<dyn Error + Send + Sync + 'static>> {
+    let mut sources = Vec::new();
+    for dir in dirs {
+        if !std::path::Path::new(dir).is_dir() {
+            return Err(format!("{} is not a directory", dir).into());
+        }
+
+        for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => return Err(format!("could not walk {}: {}", dir, e).into()),
+            };
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let matches = entry
+                .path()
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| DIR_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+                .unwrap_or(false);
+            if matches {
+                sources.push(Source::File(entry.into_path()));
+            }
+        }
+    }
+    Ok(sources)
+}
+
+/// Expands the given paths into capture file sources, see `file_sources`.
+pub fn pcap_sources(
+    patterns: &[&str],
+) -> Result<Vec<Source>, Box<dyn Error + Send + Sync + 'static>> {
+    Ok(file_sources(patterns)?
+        .into_iter()
+        .map(|source| match source {
+            Source::File(path) => Source::Pcap(path),
+            source => source,
+        })
+        .collect())
+}
+
+/// Merges file sources into a single source that follows every file.
+pub fn follow_source(sources: Vec<Source>) -> Source {
+    Source::Follow(
+        sources
+            .into_iter()
+            .filter_map(|source| match source {
+                Source::File(path) => Some(path),
+                _ => None,
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,166 @@
+use etherparse::{SlicedPacket, TransportSlice};
+use pcap_parser::{Block, Linktype, PcapBlockOwned, PcapError};
+use std::error::Error;
+use std::path::Path;
+
+/// Size of the read buffer, large enough for any single block.
+const BUFFER_SIZE: usize = 1 << 20;
+
+/// Request methods that mark the start of a plaintext http request.
+const HTTP_METHODS: [&[u8]; 9] = [
+    b"GET ",
+    b"POST ",
+    b"PUT ",
+    b"HEAD ",
+    b"DELETE ",
+    b"OPTIONS ",
+    b"PATCH ",
+    b"CONNECT ",
+    b"TRACE ",
+];
+
+/// Returns the hostnames seen in a pcap or pcapng capture, taken from the SNI
+/// of TLS ClientHello messages and the Host header of plaintext http
+/// requests. Packets are looked at one at a time, without stream reassembly.
+pub fn hosts(path: &Path) -> Result<Vec<String>, Box<dyn Error + Send + Sync + 'static>> {
+    let file = std::fs::File::open(path)?;
+    let mut reader = match pcap_parser::create_reader(BUFFER_SIZE, file) {
+        Ok(reader) => reader,
+        Err(e) => return Err(format!("not a pcap file: {:?}", e).into()),
+    };
+
+    let mut hosts = Vec::new();
+    let mut linktypes = Vec::new();
+    loop {
+        match reader.next() {
+            Ok((offset, block)) => {
+                match block {
+                    PcapBlockOwned::LegacyHeader(header) => linktypes = vec![header.network],
+                    PcapBlockOwned::Legacy(packet) => {
+                        let linktype = linktypes.first().copied().unwrap_or(Linktype::ETHERNET);
+                        hosts.extend(packet_host(linktype, packet.data));
+                    }
+                    PcapBlockOwned::NG(Block::SectionHeader(_)) => linktypes.clear(),
+                    PcapBlockOwned::NG(Block::InterfaceDescription(interface)) => {
+                        linktypes.push(interface.linktype)
+                    }
+                    PcapBlockOwned::NG(Block::EnhancedPacket(packet)) => {
+                        let linktype = linktypes
+                            .get(packet.if_id as usize)
+                            .copied()
+                            .unwrap_or(Linktype::ETHERNET);
+                        hosts.extend(packet_host(linktype, packet.data));
+                    }
+                    PcapBlockOwned::NG(Block::SimplePacket(packet)) => {
+                        let linktype = linktypes.first().copied().unwrap_or(Linktype::ETHERNET);
+                        hosts.extend(packet_host(linktype, packet.data));
+                    }
+                    _ => {}
+                }
+                reader.consume(offset);
+            }
+            Err(PcapError::Eof) => break,
+            Err(PcapError::Incomplete(_)) => {
+                if reader.refill().is_err() {
+                    return Err("could not read capture".into());
+                }
+            }
+            Err(e) => return Err(format!("error parsing capture: {:?}", e).into()),
+        }
+    }
+    Ok(hosts)
+}
+
+/// Returns the hostname carried by a single captured frame, if any.
+fn packet_host(linktype: Linktype, data: &[u8]) -> Option<String> {
+    let packet = match linktype {
+        Linktype::ETHERNET => SlicedPacket::from_ethernet(data),
+        Linktype::LINUX_SLL => SlicedPacket::from_linux_sll(data),
+        Linktype::RAW | Linktype::IPV4 | Linktype::IPV6 => SlicedPacket::from_ip(data),
+        // loopback frames carry a four byte address family before the ip header
+        Linktype::NULL | Linktype::LOOP if data.len() > 4 => SlicedPacket::from_ip(&data[4..]),
+        _ => return None,
+    }
+    .ok()?;
+
+    let payload = match packet.transport? {
+        TransportSlice::Tcp(tcp) => tcp.payload(),
+        _ => return None,
+    };
+
+    client_hello_sni(payload).or_else(|| http_host(payload))
+}
+
+/// Reads the server_name extension from a TLS ClientHello record.
+fn client_hello_sni(data: &[u8]) -> Option<String> {
+    // record header: handshake content type, version and length
+    if data.len() < 5 || data[0] != 0x16 {
+        return None;
+    }
+    let record = data.get(5..)?;
+
+    // handshake header: client hello type and a three byte length
+    if record.first()? != &0x01 {
+        return None;
+    }
+
+    // skip the handshake header, client version and random
+    let mut pos = 4 + 2 + 32;
+    let session_len = *record.get(pos)? as usize;
+    pos += 1 + session_len;
+    let ciphers_len = u16_at(record, pos)? as usize;
+    pos += 2 + ciphers_len;
+    let compression_len = *record.get(pos)? as usize;
+    pos += 1 + compression_len;
+    let extensions_len = u16_at(record, pos)? as usize;
+    pos += 2;
+
+    let end = (pos + extensions_len).min(record.len());
+    while pos + 4 <= end {
+        let kind = u16_at(record, pos)?;
+        let len = u16_at(record, pos + 2)? as usize;
+        pos += 4;
+        if kind == 0x0000 {
+            // server name list: list length, then entries of type, length and name
+            let list = record.get(pos..pos + len)?;
+            let name_type = *list.get(2)?;
+            let name_len = u16_at(list, 3)? as usize;
+            if name_type != 0 {
+                return None;
+            }
+            let name = list.get(5..5 + name_len)?;
+            return std::str::from_utf8(name).ok().map(|name| name.to_string());
+        }
+        pos += len;
+    }
+    None
+}
+
+/// Reads the Host header from the start of a plaintext http request.
+fn http_host(data: &[u8]) -> Option<String> {
+    if !HTTP_METHODS.iter().any(|method| data.starts_with(method)) {
+        return None;
+    }
+
+    let text = String::from_utf8_lossy(data);
+    text.split("\r\n")
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            if !name.trim().eq_ignore_ascii_case("host") {
+                return None;
+            }
+            let value = value.trim();
+            let host = match value.rsplit_once(':') {
+                Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+                _ => value,
+            };
+            Some(host.to_string())
+        })
+}
+
+fn u16_at(data: &[u8], pos: usize) -> Option<u16> {
+    let bytes = data.get(pos..pos + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
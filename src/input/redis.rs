@@ -0,0 +1,181 @@
+use ::redis::streams::{StreamReadOptions, StreamReadReply};
+use ::redis::{Client, Commands, Connection, ErrorKind, RedisError, RedisResult, Value};
+use async_std::channel;
+use futures::channel::oneshot;
+use std::error::Error;
+use std::time::Duration;
+
+use super::{channel_reader, Reader};
+
+/// How many times in a row reconnecting may fail before giving up.
+const MAX_RETRIES: u32 = 5;
+
+/// How long to wait for a key that does not exist yet, or for more entries
+/// on a list, before looking at the key again.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Waits for hosts on a Redis list or stream until the process is stopped.
+/// Lists are popped with BLPOP, streams are read through a consumer group so
+/// several hostparser workers can share one queue, each entry being
+/// acknowledged once it has been handed to the parser. A key that does not
+/// exist yet is read as whichever it becomes, and a lost connection is
+/// reconnected.
+pub async fn open(
+    url: &str,
+    key: &str,
+    group: &str,
+) -> Result<Reader, Box<dyn Error + Send + Sync + 'static>> {
+    let (ready_tx, ready_rx) = oneshot::channel();
+    let (tx, rx) = channel::bounded(1024);
+    let url = url.to_string();
+    let key = key.to_string();
+    let group = group.to_string();
+
+    std::thread::spawn(move || {
+        let connected = Client::open(url).and_then(|client| {
+            let mut con = client.get_connection()?;
+            let kind: String = con.key_type(&key)?;
+            Ok((client, con, kind))
+        });
+        let (client, mut con) = match connected {
+            Ok((_, _, kind)) if !matches!(kind.as_str(), "list" | "stream" | "none") => {
+                let _ = ready_tx.send(Err(format!("{} is a {}, not a list or stream", key, kind)));
+                return;
+            }
+            Ok((client, con, _)) => (client, con),
+            Err(e) => {
+                let _ = ready_tx.send(Err(e.to_string()));
+                return;
+            }
+        };
+        let _ = ready_tx.send(Ok(()));
+
+        let mut attempts = 0;
+        loop {
+            let mut e = match read(&mut con, &key, &group, &tx) {
+                // the parser has stopped reading
+                Ok(()) => return,
+                Err(e) => e,
+            };
+            loop {
+                attempts += 1;
+                if attempts > MAX_RETRIES {
+                    warn!(
+                        "error reading {}: {}, giving up after {} retries",
+                        key, e, MAX_RETRIES
+                    );
+                    return;
+                }
+                warn!(
+                    "error reading {}: {}, retrying ({}/{})",
+                    key, e, attempts, MAX_RETRIES
+                );
+                std::thread::sleep(Duration::from_secs(1 << (attempts - 1)));
+                match client.get_connection() {
+                    Ok(reconnected) => {
+                        con = reconnected;
+                        attempts = 0;
+                        break;
+                    }
+                    Err(failed) => e = failed,
+                }
+            }
+        }
+    });
+
+    match ready_rx.await {
+        Ok(Ok(())) => Ok(channel_reader(rx)),
+        Ok(Err(e)) => Err(e.into()),
+        Err(_) => Err("redis thread exited".into()),
+    }
+}
+
+/// Reads the key as a list or a stream, whichever it is, until the parser
+/// stops reading or the connection fails.
+fn read(
+    con: &mut Connection,
+    key: &str,
+    group: &str,
+    tx: &channel::Sender<String>,
+) -> RedisResult<()> {
+    loop {
+        let kind: String = con.key_type(key)?;
+        let more = match kind.as_str() {
+            // nothing has been added to it yet
+            "none" => {
+                std::thread::sleep(POLL_INTERVAL);
+                true
+            }
+            "list" => pop_list(con, key, tx)?,
+            "stream" => {
+                // the group may already exist from an earlier worker
+                let _: Result<(), _> = con.xgroup_create_mkstream(key, group, "0");
+                read_stream(con, key, group, tx)?
+            }
+            kind => {
+                let message = format!("{} is a {}, not a list or stream", key, kind);
+                return Err(RedisError::from((
+                    ErrorKind::TypeError,
+                    "wrong type",
+                    message,
+                )));
+            }
+        };
+        if !more {
+            return Ok(());
+        }
+    }
+}
+
+/// Pops hosts off a list for as long as the key is one. Returns whether to
+/// go on reading, which is not the case once the parser stops.
+fn pop_list(con: &mut Connection, key: &str, tx: &channel::Sender<String>) -> RedisResult<bool> {
+    loop {
+        let popped: Option<(String, String)> = con.blpop(key, POLL_INTERVAL.as_secs_f64())?;
+        match popped {
+            Some((_, host)) => {
+                if tx.send_blocking(host).is_err() {
+                    return Ok(false);
+                }
+            }
+            // an emptied list is gone, and the key may come back as a stream
+            None => {
+                let kind: String = con.key_type(key)?;
+                if kind != "list" && kind != "none" {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+}
+
+/// Reads the entries of a stream as a member of the consumer group. Returns
+/// whether to go on reading, which is not the case once the parser stops.
+fn read_stream(
+    con: &mut Connection,
+    key: &str,
+    group: &str,
+    tx: &channel::Sender<String>,
+) -> RedisResult<bool> {
+    let consumer = format!("hostparser-{}", std::process::id());
+    let options = StreamReadOptions::default()
+        .group(group, &consumer)
+        .block(0)
+        .count(100);
+    loop {
+        let reply: StreamReadReply = con.xread_options(&[key], &[">"], &options)?;
+        for entry in reply.keys.iter().flat_map(|stream| stream.ids.iter()) {
+            // prefer a host field, otherwise take the first value of the entry
+            let value = entry.map.get("host").or_else(|| entry.map.values().next());
+            if let Some(Value::BulkString(host)) = value {
+                if tx
+                    .send_blocking(String::from_utf8_lossy(host).into_owned())
+                    .is_err()
+                {
+                    return Ok(false);
+                }
+            }
+            let _: Result<(), _> = con.xack(key, group, &[&entry.id]);
+        }
+    }
+}
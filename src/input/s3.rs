@@ -0,0 +1,180 @@
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{http, Reader};
+
+/// Body hash sent instead of hashing the (empty) request payload.
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Credentials and location taken from the standard AWS environment
+/// variables.
+struct Credentials {
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+    region: String,
+}
+
+/// Streams an object from S3 or an S3 compatible store given as
+/// `s3://bucket/key`. Requests are signed with AWS signature version 4 when
+/// credentials are set in the environment and sent anonymously otherwise.
+/// `AWS_ENDPOINT_URL` selects a custom endpoint, addressed path style.
+pub async fn open(location: &str) -> Result<Reader, Box<dyn Error + Send + Sync + 'static>> {
+    let (bucket, key) = match location
+        .strip_prefix("s3://")
+        .and_then(|rest| rest.split_once('/'))
+    {
+        Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() => (bucket, key),
+        _ => return Err(format!("{} is not of the form s3://bucket/key", location).into()),
+    };
+
+    let region = std::env::var("AWS_REGION")
+        .or_else(|_| std::env::var("AWS_DEFAULT_REGION"))
+        .unwrap_or_else(|_| "us-east-1".to_string());
+    let path = format!("/{}", uri_encode(key));
+    let url = match std::env::var("AWS_ENDPOINT_URL") {
+        Ok(endpoint) => format!("{}/{}{}", endpoint.trim_end_matches('/'), bucket, path),
+        Err(_) => format!("https://{}.s3.{}.amazonaws.com{}", bucket, region, path),
+    };
+
+    let credentials = match (
+        std::env::var("AWS_ACCESS_KEY_ID"),
+        std::env::var("AWS_SECRET_ACCESS_KEY"),
+    ) {
+        (Ok(access_key), Ok(secret_key)) => Credentials {
+            access_key,
+            secret_key,
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+            region,
+        },
+        _ => return http::open(&url).await,
+    };
+
+    let parsed = reqwest::Url::parse(&url)?;
+    let host = match (parsed.host_str(), parsed.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        _ => return Err(format!("invalid endpoint {}", url).into()),
+    };
+    let canonical_uri = parsed.path().to_string();
+
+    let sign = Box::new(move || sign_request(&credentials, &host, &canonical_uri));
+    http::open_signed(&url, Some(sign)).await
+}
+
+/// Builds the signature version 4 headers for a GET of the given path.
+fn sign_request(credentials: &Credentials, host: &str, path: &str) -> HeaderMap {
+    let (date, timestamp) = amz_date(SystemTime::now());
+    let scope = format!("{}/{}/s3/aws4_request", date, credentials.region);
+
+    let mut headers = vec![
+        ("host", host.to_string()),
+        ("x-amz-content-sha256", UNSIGNED_PAYLOAD.to_string()),
+        ("x-amz-date", timestamp.clone()),
+    ];
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let canonical_request = format!(
+        "GET\n{}\n\n{}\n{}\n{}",
+        path, canonical_headers, signed_headers, UNSIGNED_PAYLOAD
+    );
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        timestamp,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = format!("AWS4{}", credentials.secret_key);
+    let key = hmac(key.as_bytes(), date.as_bytes());
+    let key = hmac(&key, credentials.region.as_bytes());
+    let key = hmac(&key, b"s3");
+    let key = hmac(&key, b"aws4_request");
+    let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+
+    let mut map = HeaderMap::new();
+    for (name, value) in headers.into_iter().filter(|(name, _)| *name != "host") {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            map.insert(name, value);
+        }
+    }
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        credentials.access_key, scope, signed_headers, signature
+    );
+    if let Ok(value) = HeaderValue::from_str(&authorization) {
+        map.insert(AUTHORIZATION, value);
+    }
+    map
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Percent-encodes everything but the unreserved characters and `/`.
+fn uri_encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(b as char)
+            }
+            b'/' => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    encoded
+}
+
+/// Formats the time as the `YYYYMMDD` date and `YYYYMMDDTHHMMSSZ` timestamp
+/// used in signatures.
+fn amz_date(time: SystemTime) -> (String, String) {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+
+    // civil date from days since the epoch, see Howard Hinnant's date algorithms
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    let date = format!("{:04}{:02}{:02}", year, month, day);
+    let timestamp = format!(
+        "{}T{:02}{:02}{:02}Z",
+        date,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    );
+    (date, timestamp)
+}
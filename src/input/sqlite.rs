@@ -0,0 +1,73 @@
+use async_std::channel;
+use futures::channel::oneshot;
+use rusqlite::types::ValueRef;
+use rusqlite::Connection;
+use std::error::Error;
+use std::io;
+use std::path::Path;
+
+use super::{fallible_reader, Reader};
+
+/// Runs the query against a local SQLite database and streams the first
+/// column of every row as a host. Rows are read on a separate thread so large
+/// tables never have to fit in memory.
+pub async fn open(
+    path: &Path,
+    query: &str,
+) -> Result<Reader, Box<dyn Error + Send + Sync + 'static>> {
+    let (ready_tx, ready_rx) = oneshot::channel();
+    let (tx, rx) = channel::bounded(1024);
+    let path = path.to_path_buf();
+    let query = query.to_string();
+
+    std::thread::spawn(move || {
+        let conn =
+            match Connection::open_with_flags(&path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY) {
+                Ok(conn) => conn,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e.to_string()));
+                    return;
+                }
+            };
+        let mut stmt = match conn.prepare(&query) {
+            Ok(stmt) => stmt,
+            Err(e) => {
+                let _ = ready_tx.send(Err(e.to_string()));
+                return;
+            }
+        };
+        let mut rows = match stmt.query([]) {
+            Ok(rows) => rows,
+            Err(e) => {
+                let _ = ready_tx.send(Err(e.to_string()));
+                return;
+            }
+        };
+        let _ = ready_tx.send(Ok(()));
+
+        loop {
+            let host = match rows.next() {
+                Ok(Some(row)) => match row.get_ref(0) {
+                    Ok(ValueRef::Text(text)) => String::from_utf8_lossy(text).into_owned(),
+                    _ => continue,
+                },
+                Ok(None) => break,
+                // the reader reports it, so the run fails rather than
+                // passing for a complete one
+                Err(e) => {
+                    let _ = tx.send_blocking(Err(io::Error::other(e)));
+                    break;
+                }
+            };
+            if tx.send_blocking(Ok(host)).is_err() {
+                break;
+            }
+        }
+    });
+
+    match ready_rx.await {
+        Ok(Ok(())) => Ok(fallible_reader(rx)),
+        Ok(Err(e)) => Err(e.into()),
+        Err(_) => Err("query thread exited".into()),
+    }
+}
@@ -0,0 +1,47 @@
+//! Diagnostics written to stderr, so stdout only ever carries results.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Errors that stop the run are always written, warnings unless `-q` is
+/// given, and the rest only with `-v` or `-vv`.
+pub const QUIET: u8 = 0;
+pub const WARN: u8 = 1;
+pub const INFO: u8 = 2;
+pub const DEBUG: u8 = 3;
+
+static LEVEL: AtomicU8 = AtomicU8::new(WARN);
+
+pub fn set_level(level: u8) {
+    LEVEL.store(level, Ordering::Relaxed);
+}
+
+pub fn enabled(level: u8) -> bool {
+    LEVEL.load(Ordering::Relaxed) >= level
+}
+
+/// Problems the run carries on after, such as an unreadable input.
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::WARN) {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+/// What the run is doing, with `-v`.
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::INFO) {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+/// Details about single hosts, with `-vv`.
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::DEBUG) {
+            eprintln!($($arg)*);
+        }
+    };
+}
@@ -1,29 +1,710 @@
 use async_std::io;
 use async_std::io::prelude::*;
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches};
 use futures::{stream::FuturesUnordered, StreamExt};
+use governor::clock::DefaultClock;
+use governor::state::{InMemoryState, NotKeyed};
 use governor::{Quota, RateLimiter};
+use std::borrow::Cow;
 use std::error::Error;
-use tldextract::{TldExtractor, TldOption};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tldextract::TldExtractor;
 use tokio::{runtime::Builder, task};
 
+#[macro_use]
+mod log;
+mod checkpoint;
+mod entropy;
+mod format;
+mod input;
+mod normalize;
+mod output;
+mod permute;
+mod progress;
+mod psl;
+mod server;
+mod stats;
+mod typosquat;
+
+use checkpoint::{Checkpointer, Position};
+use entropy::{Entropy, Score};
+use format::{FormatOptions, Host, InputFormat};
+use input::encoding::InputEncoding;
+use input::Source;
+use normalize::{Form, Idn, Ips, Ports, Unknown};
+use output::{Compression, Field, Output, OutputFormat, OutputOptions, SortOrder, Template};
+use progress::Progress;
+use stats::Stats;
+use typosquat::Seeds;
+
 #[derive(Clone, Debug)]
 pub struct Job {
     host: Option<String>,
+    sources: Vec<String>,
+    mailbox: Option<String>,
+    reply: Option<async_std::channel::Sender<String>>,
+    /// The position of the host among all hosts read, for ordered output.
+    seq: u64,
 }
 
 #[derive(Clone, Debug)]
 pub struct JobResult {
-    pub data: String,
+    pub input: String,
+    pub subdomain: String,
+    pub domain: String,
+    pub suffix: String,
+    pub sources: Vec<String>,
+    pub mailbox: Option<String>,
+    /// The port the host was given with, when ports are kept.
+    pub port: Option<String>,
+    /// Whether the host was a wildcard, when wildcards are tagged.
+    pub wildcard: bool,
+    /// Whether the host ended in a dot, when the dot is kept.
+    pub fqdn: bool,
+    /// Whether the suffix is not on the public suffix list, when such hosts
+    /// are kept.
+    pub unknown: bool,
+    /// Whether the host looks machine generated, when hosts are scored.
+    pub dga: bool,
+    /// The seed domain the root domain is a lookalike of and how, when
+    /// lookalikes are searched for.
+    pub squat: Option<String>,
+}
+
+impl JobResult {
+    /// The registrable domain, e.g. `example.co.uk`, or the whole host when
+    /// it was not split up.
+    pub fn root(&self) -> String {
+        let dot = if self.fqdn { "." } else { "" };
+        if self.is_whole() {
+            return format!("{}{}", self.domain, dot);
+        }
+        format!("{}.{}{}", self.domain, self.suffix, dot)
+    }
+
+    /// The host the result was extracted from, put back together.
+    pub fn host(&self) -> String {
+        if self.subdomain.is_empty() {
+            self.root()
+        } else {
+            format!("{}.{}", self.subdomain, self.root())
+        }
+    }
+
+    /// Whether the host was kept whole as the domain, without a suffix,
+    /// because it is an ip address or was not meant to be extracted.
+    pub fn is_whole(&self) -> bool {
+        self.suffix.is_empty()
+    }
+
+    pub fn is_ip(&self) -> bool {
+        self.is_whole() && normalize::ip(&self.domain).is_some()
+    }
+
+    /// How random the host looks, leaving its suffix out.
+    pub fn score(&self) -> Score {
+        Score::new(&format!("{}.{}", self.subdomain, self.domain))
+    }
+
+    /// How many labels the host has above its registrable domain.
+    pub fn depth(&self) -> usize {
+        self.subdomain.split('.').filter(|l| !l.is_empty()).count()
+    }
+
+    /// The parents of the host, from the closest down to the registrable
+    /// domain, which is always among them.
+    pub fn ancestors(&self) -> Vec<JobResult> {
+        let labels: Vec<&str> = self
+            .subdomain
+            .split('.')
+            .filter(|l| !l.is_empty())
+            .collect();
+        if self.is_whole() || labels.is_empty() {
+            return vec![self.clone()];
+        }
+        (1..=labels.len())
+            .rev()
+            .map(|depth| {
+                let (rest, kept) = labels.split_at(labels.len() - depth + 1);
+                let mut domain = kept.join(".");
+                if !domain.is_empty() {
+                    domain.push('.');
+                }
+                domain.push_str(&self.domain);
+                JobResult {
+                    subdomain: rest.join("."),
+                    domain,
+                    ..self.clone()
+                }
+            })
+            .collect()
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
     // parse the cli arguments
-    let matches = App::new("hostparser")
+    let app = App::new("hostparser")
         .version("0.1.3")
         .author("Blake Jacobs <krypt0mux@gmail.com>")
         .about("A very fast hostparser")
+        .arg(
+            Arg::with_name("hosts")
+                .multiple_values(true)
+                .help("Hosts to parse, read instead of stdin when it is a terminal"),
+        )
+        .arg(
+            Arg::with_name("file")
+                .short('f')
+                .long("file")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .display_order(1)
+                .help("Read hosts from a file or glob instead of stdin, can be repeated"),
+        )
+        .arg(
+            Arg::with_name("follow")
+                .long("follow")
+                .requires("file")
+                .display_order(1)
+                .help("Keep the input files open and parse new lines as they are appended"),
+        )
+        .arg(
+            Arg::with_name("dir")
+                .long("dir")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .display_order(1)
+                .help("Recursively read every .txt and .lst file in a directory"),
+        )
+        .arg(
+            Arg::with_name("url")
+                .long("url")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .display_order(1)
+                .help("Stream hosts from a remote http(s) url, can be repeated"),
+        )
+        .arg(
+            Arg::with_name("s3")
+                .long("s3")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .display_order(1)
+                .help("Stream hosts from an S3 object, e.g. s3://bucket/hosts.txt.gz"),
+        )
+        .arg(
+            Arg::with_name("pcap")
+                .long("pcap")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .display_order(1)
+                .help("Read hostnames from the TLS SNI and http Host headers in a pcap file"),
+        )
+        .arg(
+            Arg::with_name("sqlite")
+                .long("sqlite")
+                .takes_value(true)
+                .requires("query")
+                .display_order(1)
+                .help("Read hosts from a SQLite database"),
+        )
+        .arg(
+            Arg::with_name("query")
+                .long("query")
+                .takes_value(true)
+                .requires("sqlite")
+                .display_order(1)
+                .help("The query to run against --sqlite, hosts are read from the first column"),
+        )
+        .arg(
+            Arg::with_name("redis")
+                .long("redis")
+                .takes_value(true)
+                .requires("key")
+                .display_order(1)
+                .help("Wait for hosts on a Redis list or stream, e.g. redis://host/0"),
+        )
+        .arg(
+            Arg::with_name("key")
+                .long("key")
+                .takes_value(true)
+                .requires("redis")
+                .display_order(1)
+                .help("The Redis list or stream key to read hosts from"),
+        )
+        .arg(
+            Arg::with_name("redis-group")
+                .long("redis-group")
+                .takes_value(true)
+                .default_value("hostparser")
+                .display_order(1)
+                .help("The consumer group used when --key is a stream"),
+        )
+        .arg(
+            Arg::with_name("certstream")
+                .long("certstream")
+                .takes_value(true)
+                .min_values(0)
+                .default_missing_value("wss://certstream.calidog.io/")
+                .value_name("URL")
+                .display_order(1)
+                .help("Follow new certificates from a CertStream server, calidog.io by default"),
+        )
+        .arg(
+            Arg::with_name("listen-unix")
+                .long("listen-unix")
+                .takes_value(true)
+                .display_order(1)
+                .help("Serve hosts written to a unix socket, answering with their root domains"),
+        )
+        .arg(
+            Arg::with_name("serve")
+                .long("serve")
+                .takes_value(true)
+                .value_name("ADDR")
+                .display_order(1)
+                .help("Serve POST /parse over http on an address such as 127.0.0.1:8080"),
+        )
+        .arg(
+            Arg::with_name("max-body")
+                .long("max-body")
+                .takes_value(true)
+                .value_name("BYTES")
+                .default_value("10485760")
+                .display_order(1)
+                .help("The largest request body --serve reads, bigger ones are refused"),
+        )
+        .arg(
+            Arg::with_name("input-format")
+                .long("input-format")
+                .takes_value(true)
+                .possible_values([
+                    "text", "jsonl", "csv", "nmap", "masscan", "amass", "burp", "massdns", "zone",
+                    "hosts", "pem", "der", "email", "html", "grep", "js", "sitemap", "robots",
+                ])
+                .default_value("text")
+                .display_order(4)
+                .help("The format of the input"),
+        )
+        .arg(
+            Arg::with_name("grep")
+                .long("grep")
+                .display_order(4)
+                .help("Scan any text such as logs or responses for hosts, the same as --input-format grep"),
+        )
+        .arg(
+            Arg::with_name("field")
+                .long("field")
+                .takes_value(true)
+                .default_value("host")
+                .display_order(4)
+                .help("The dotted path of the host field for jsonl input"),
+        )
+        .arg(
+            Arg::with_name("column")
+                .long("column")
+                .takes_value(true)
+                .default_value("host")
+                .display_order(4)
+                .help("The header name or zero based index of the host column for csv input"),
+        )
+        .arg(
+            Arg::with_name("no-header")
+                .long("no-header")
+                .display_order(4)
+                .help("The csv input has no header row, requires an index for --column"),
+        )
+        .arg(
+            Arg::with_name("masscan-ips")
+                .long("masscan-ips")
+                .display_order(4)
+                .help("Also emit the scanned addresses from masscan input"),
+        )
+        .arg(
+            Arg::with_name("amass-sources")
+                .long("amass-sources")
+                .display_order(4)
+                .help("Print the data sources from amass input next to each result"),
+        )
+        .arg(
+            Arg::with_name("keep-mailbox")
+                .long("keep-mailbox")
+                .display_order(4)
+                .help("Print email input as the mailbox at its root domain instead of the domain alone"),
+        )
+        .arg(
+            Arg::with_name("encoding")
+                .long("encoding")
+                .takes_value(true)
+                .default_value("auto")
+                .display_order(4)
+                .help("The encoding of the input, e.g. utf-16le or latin1, detected by default"),
+        )
+        .arg(
+            Arg::with_name("no-comments")
+                .long("no-comments")
+                .display_order(4)
+                .help("Read # as part of the hosts rather than the start of a comment"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short('o')
+                .long("output")
+                .takes_value(true)
+                .value_name("FILE")
+                .multiple_occurrences(true)
+                .display_order(4)
+                .help("Write the results to a file instead of stdout, - for stdout, may be repeated"),
+        )
+        .arg(
+            Arg::with_name("webhook")
+                .long("webhook")
+                .takes_value(true)
+                .value_name("URL")
+                .multiple_occurrences(true)
+                .display_order(4)
+                .help("Post every new root domain to a url as json, in batches"),
+        )
+        .arg(
+            Arg::with_name("syslog")
+                .long("syslog")
+                .takes_value(true)
+                .value_name("URL")
+                .multiple_occurrences(true)
+                .display_order(4)
+                .help("Send every root domain to syslog at udp://host:port, tcp://host:port or unix:///dev/log"),
+        )
+        .arg(
+            Arg::with_name("elastic")
+                .long("elastic")
+                .takes_value(true)
+                .value_name("URL")
+                .display_order(4)
+                .help("Index every result into Elasticsearch through the bulk api"),
+        )
+        .arg(
+            Arg::with_name("index")
+                .long("index")
+                .takes_value(true)
+                .default_value("hostparser")
+                .display_order(4)
+                .help("The Elasticsearch index --elastic writes to"),
+        )
+        .arg(
+            Arg::with_name("split-by")
+                .long("split-by")
+                .takes_value(true)
+                .possible_values(["suffix", "root"])
+                .requires("output-dir")
+                .conflicts_with_all(&["count", "top", "tree"])
+                .display_order(4)
+                .help("Write each result to a file named after its suffix or root domain"),
+        )
+        .arg(
+            Arg::with_name("output-dir")
+                .long("output-dir")
+                .takes_value(true)
+                .value_name("DIR")
+                .requires("split-by")
+                .display_order(4)
+                .help("The directory --split-by writes its files to"),
+        )
+        .arg(
+            Arg::with_name("append")
+                .long("append")
+                .display_order(4)
+                .help("Add the results to the end of the output files instead of replacing them"),
+        )
+        .arg(
+            Arg::with_name("compress")
+                .long("compress")
+                .takes_value(true)
+                .possible_values(["gzip", "zstd"])
+                .display_order(4)
+                .help("Compress the output, otherwise files ending in .gz or .zst are compressed"),
+        )
+        .arg(
+            Arg::with_name("output-format")
+                .long("output-format")
+                .takes_value(true)
+                .possible_values(["text", "json", "jsonl", "csv", "tsv", "xml", "dot"])
+                .default_value("text")
+                .display_order(4)
+                .help("How the results are written"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .value_name("TEMPLATE")
+                .conflicts_with("output-format")
+                .display_order(4)
+                .help("Print each result from a template, e.g. '{subdomain} {domain}.{suffix}'"),
+        )
+        .arg(
+            Arg::with_name("fields")
+                .long("fields")
+                .takes_value(true)
+                .conflicts_with_all(&["output-format", "format"])
+                .display_order(4)
+                .help("Print only these fields of each result, e.g. root,subdomain,suffix,input"),
+        )
+        .arg(
+            Arg::with_name("separator")
+                .long("separator")
+                .takes_value(true)
+                .default_value("\\t")
+                .display_order(4)
+                .help("The separator between --fields"),
+        )
+        .arg(
+            Arg::with_name("suffix-only")
+                .long("suffix-only")
+                .conflicts_with_all(&["output-format", "format", "fields"])
+                .display_order(4)
+                .help("Print only the public suffix of each host, e.g. co.uk"),
+        )
+        .arg(
+            Arg::with_name("subdomain-only")
+                .long("subdomain-only")
+                .conflicts_with_all(&["output-format", "format", "fields", "suffix-only"])
+                .display_order(4)
+                .help("Print only the subdomain of each host, e.g. dev.api"),
+        )
+        .arg(
+            Arg::with_name("split-labels")
+                .long("split-labels")
+                .requires("subdomain-only")
+                .display_order(4)
+                .help("Print each label of the subdomain on its own line"),
+        )
+        .arg(
+            Arg::with_name("with-input")
+                .long("with-input")
+                .conflicts_with_all(&[
+                    "output-format",
+                    "format",
+                    "fields",
+                    "suffix-only",
+                    "subdomain-only",
+                ])
+                .display_order(4)
+                .help("Print each host next to its root domain, separated by a tab"),
+        )
+        .arg(
+            Arg::with_name("depth")
+                .long("depth")
+                .conflicts_with_all(&[
+                    "output-format",
+                    "format",
+                    "fields",
+                    "suffix-only",
+                    "subdomain-only",
+                    "with-input",
+                ])
+                .display_order(4)
+                .help("Print each host with how many labels deep it is below its root domain"),
+        )
+        .arg(
+            Arg::with_name("reverse-notation")
+                .long("reverse-notation")
+                .conflicts_with_all(&[
+                    "output-format",
+                    "format",
+                    "fields",
+                    "suffix-only",
+                    "subdomain-only",
+                    "with-input",
+                    "depth",
+                ])
+                .display_order(4)
+                .help("Print each host with its labels reversed, such as com.example.api"),
+        )
+        .arg(
+            Arg::with_name("surt")
+                .long("surt")
+                .conflicts_with_all(&[
+                    "output-format",
+                    "format",
+                    "fields",
+                    "suffix-only",
+                    "subdomain-only",
+                    "with-input",
+                    "depth",
+                    "reverse-notation",
+                ])
+                .display_order(4)
+                .help("Print the canonical SURT key of each host or url, such as com,example)/"),
+        )
+        .arg(
+            Arg::with_name("typosquat")
+                .long("typosquat")
+                .takes_value(true)
+                .value_name("DOMAINS")
+                .multiple_occurrences(true)
+                .conflicts_with_all(&["no-extract", "level", "output-format"])
+                .display_order(4)
+                .help("Only print the lookalikes of these comma separated apex domains, with the domain each imitates and how, may be repeated"),
+        )
+        .arg(
+            Arg::with_name("entropy")
+                .long("entropy")
+                .takes_value(true)
+                .possible_values(["tag", "drop", "only"])
+                .display_order(4)
+                .help("Score hosts on how machine generated they look, and print the scores, drop the likely DGA hosts or only print those"),
+        )
+        .arg(
+            Arg::with_name("entropy-threshold")
+                .long("entropy-threshold")
+                .takes_value(true)
+                .value_name("BITS")
+                .default_value("3.0")
+                .display_order(4)
+                .help("The entropy of a label, in bits, above which --entropy takes it for generated"),
+        )
+        .arg(
+            Arg::with_name("print0")
+                .long("print0")
+                .conflicts_with("output-format")
+                .display_order(4)
+                .help("Separate the results with NUL bytes instead of newlines, for xargs -0"),
+        )
+        .arg(
+            Arg::with_name("no-color")
+                .long("no-color")
+                .display_order(4)
+                .help("Never color the output, even on a terminal"),
+        )
+        .arg(
+            Arg::with_name("unique")
+                .short('u')
+                .long("unique")
+                .display_order(4)
+                .help("Only print each root domain once"),
+        )
+        .arg(
+            Arg::with_name("unique-approx")
+                .long("unique-approx")
+                .takes_value(true)
+                .value_name("N")
+                .conflicts_with("unique")
+                .display_order(4)
+                .help("Only print each root domain once, in bounded memory sized for N of them"),
+        )
+        .arg(
+            Arg::with_name("fp-rate")
+                .long("fp-rate")
+                .takes_value(true)
+                .default_value("0.001")
+                .display_order(4)
+                .help("The share of new root domains --unique-approx may take for duplicates"),
+        )
+        .arg(
+            Arg::with_name("ordered")
+                .long("ordered")
+                .conflicts_with_all(&[
+                    "split-labels",
+                    "expand",
+                    "sort",
+                    "count",
+                    "top",
+                    "tree",
+                    "report",
+                ])
+                .display_order(4)
+                .help("Print the results in the order of the input, a line for every host"),
+        )
+        .arg(
+            Arg::with_name("sort")
+                .long("sort")
+                .takes_value(true)
+                .min_values(0)
+                .possible_values(["root", "suffix"])
+                .default_missing_value("root")
+                .display_order(4)
+                .help("Print the results sorted once the input is done, by root or by suffix"),
+        )
+        .arg(
+            Arg::with_name("count")
+                .long("count")
+                .conflicts_with_all(&[
+                    "output-format",
+                    "format",
+                    "fields",
+                    "with-input",
+                    "unique",
+                    "unique-approx",
+                    "sort",
+                ])
+                .display_order(4)
+                .help("Print how many hosts map to each root domain, most common first"),
+        )
+        .arg(
+            Arg::with_name("top")
+                .long("top")
+                .takes_value(true)
+                .value_name("N")
+                .conflicts_with_all(&[
+                    "output-format",
+                    "format",
+                    "fields",
+                    "with-input",
+                    "unique",
+                    "unique-approx",
+                    "sort",
+                ])
+                .display_order(4)
+                .help("Print only the N most common root domains with their counts"),
+        )
+        .arg(
+            Arg::with_name("tree")
+                .long("tree")
+                .conflicts_with_all(&[
+                    "output-format",
+                    "format",
+                    "fields",
+                    "suffix-only",
+                    "subdomain-only",
+                    "with-input",
+                    "unique",
+                    "unique-approx",
+                    "sort",
+                    "count",
+                    "top",
+                ])
+                .display_order(4)
+                .help("Print the subdomains grouped under each root domain, one level per label"),
+        )
+        .arg(
+            Arg::with_name("report")
+                .long("report")
+                .takes_value(true)
+                .possible_values(["md"])
+                .conflicts_with_all(&[
+                    "output-format",
+                    "format",
+                    "fields",
+                    "with-input",
+                    "sort",
+                    "count",
+                    "top",
+                    "tree",
+                ])
+                .display_order(4)
+                .help("Print a markdown report of the root domains and suffixes once the input is done"),
+        )
+        .arg(
+            Arg::with_name("delimiter")
+                .long("delimiter")
+                .takes_value(true)
+                .display_order(4)
+                .help("The delimiter of csv and tsv output"),
+        )
         .arg(
             Arg::with_name("rate")
                 .short('r')
@@ -51,12 +732,377 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
                 .display_order(5)
                 .help("The amount of workers"),
         )
-        .get_matches();
+        .arg(
+            Arg::with_name("silent")
+                .short('q')
+                .long("silent")
+                .conflicts_with("verbose")
+                .display_order(7)
+                .help("Only print errors that stop the run besides the results"),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .short('v')
+                .long("verbose")
+                .multiple_occurrences(true)
+                .display_order(7)
+                .help("Print what the run is doing to stderr, -vv for every host that fails to parse"),
+        )
+        .arg(
+            Arg::with_name("ports")
+                .long("ports")
+                .takes_value(true)
+                .possible_values(["strip", "keep", "only"])
+                .default_value("strip")
+                .display_order(4)
+                .help("Drop the ports of hosts, print them, or only print hosts with one"),
+        )
+        .arg(
+            Arg::with_name("ips")
+                .long("ips")
+                .takes_value(true)
+                .possible_values(["drop", "keep", "separate"])
+                .default_value("drop")
+                .display_order(4)
+                .help("Drop hosts that are ip addresses, print them, or write them to --ip-output"),
+        )
+        .arg(
+            Arg::with_name("ip-output")
+                .long("ip-output")
+                .takes_value(true)
+                .value_name("FILE")
+                .required_if_eq("ips", "separate")
+                .display_order(4)
+                .help("The file ip addresses are written to with --ips separate"),
+        )
+        .arg(
+            Arg::with_name("extra-suffix")
+                .long("extra-suffix")
+                .takes_value(true)
+                .value_name("SUFFIXES")
+                .multiple_occurrences(true)
+                .display_order(4)
+                .help("Treat these comma separated names as public suffixes too, such as corp or ad.example.com, may be repeated"),
+        )
+        .arg(
+            Arg::with_name("internal-suffixes")
+                .long("internal-suffixes")
+                .display_order(4)
+                .help("Treat the suffixes of internal networks, such as local, lan, corp and internal, as public suffixes"),
+        )
+        .arg(
+            Arg::with_name("unknown-tld")
+                .long("unknown-tld")
+                .takes_value(true)
+                .possible_values(["drop", "keep", "separate-file"])
+                .default_value("drop")
+                .display_order(4)
+                .help("Drop hosts whose suffix is not on the public suffix list, print them, or write them to --unknown-output"),
+        )
+        .arg(
+            Arg::with_name("unknown-output")
+                .long("unknown-output")
+                .takes_value(true)
+                .value_name("FILE")
+                .required_if_eq("unknown-tld", "separate-file")
+                .display_order(4)
+                .help("The file hosts with unlisted suffixes are written to with --unknown-tld separate-file"),
+        )
+        .arg(
+            Arg::with_name("expand-cidr")
+                .long("expand-cidr")
+                .display_order(4)
+                .help("Read networks such as 10.0.0.0/28 as each of their addresses, with --ips keep"),
+        )
+        .arg(
+            Arg::with_name("cidr-limit")
+                .long("cidr-limit")
+                .takes_value(true)
+                .value_name("N")
+                .default_value("65536")
+                .display_order(4)
+                .help("The most addresses a network is expanded to"),
+        )
+        .arg(
+            Arg::with_name("nested-urls")
+                .long("nested-urls")
+                .display_order(4)
+                .help("Also parse the urls in the query strings of urls, such as the target of a redirect"),
+        )
+        .arg(
+            Arg::with_name("idn")
+                .long("idn")
+                .takes_value(true)
+                .possible_values(["unicode", "ascii"])
+                .display_order(4)
+                .help("Print internationalized domain names in unicode or in punycode"),
+        )
+        .arg(
+            Arg::with_name("unicode-normalization")
+                .long("unicode-normalization")
+                .takes_value(true)
+                .value_name("FORM")
+                .possible_values(["nfc", "nfkc"])
+                .display_order(4)
+                .help("Normalize internationalized hosts to nfc or nfkc before extracting and deduplicating them"),
+        )
+        .arg(
+            Arg::with_name("tag-wildcards")
+                .long("tag-wildcards")
+                .display_order(4)
+                .help("Mark the results of wildcard hosts such as *.example.com"),
+        )
+        .arg(
+            Arg::with_name("no-extract")
+                .long("no-extract")
+                .conflicts_with_all(&["suffix-only", "subdomain-only", "split-labels", "tree"])
+                .display_order(4)
+                .help("Only clean the hosts up and print them whole, without extracting the root domain"),
+        )
+        .arg(
+            Arg::with_name("level")
+                .short('l')
+                .long("level")
+                .takes_value(true)
+                .value_name("N")
+                .conflicts_with("no-extract")
+                .display_order(4)
+                .help("Print the domain N labels deep, counting the suffix as one, and skip hosts with fewer"),
+        )
+        .arg(
+            Arg::with_name("expand")
+                .long("expand")
+                .conflicts_with("no-extract")
+                .display_order(4)
+                .help("Print every parent of the host down to the root domain"),
+        )
+        .arg(
+            Arg::with_name("match")
+                .long("match")
+                .takes_value(true)
+                .value_name("REGEX")
+                .display_order(4)
+                .help("Only print the root domains that match a regular expression"),
+        )
+        .arg(
+            Arg::with_name("match-host")
+                .long("match-host")
+                .requires("match")
+                .display_order(4)
+                .help("Match --match against the whole host rather than its root domain"),
+        )
+        .arg(
+            Arg::with_name("strip-www")
+                .long("strip-www")
+                .display_order(4)
+                .help("Strip www. off the front of hosts"),
+        )
+        .arg(
+            Arg::with_name("strip-prefix")
+                .long("strip-prefix")
+                .takes_value(true)
+                .value_name("LABELS")
+                .multiple_occurrences(true)
+                .display_order(4)
+                .help("Strip these comma separated labels off the front of hosts, may be repeated"),
+        )
+        .arg(
+            Arg::with_name("psl")
+                .long("psl")
+                .takes_value(true)
+                .value_name("FILE")
+                .display_order(4)
+                .help("Read the public suffixes from a list in the public_suffix_list.dat format"),
+        )
+        .arg(
+            Arg::with_name("update-psl")
+                .long("update-psl")
+                .display_order(4)
+                .help("Download the public suffix list into ~/.cache/hostparser and exit"),
+        )
+        .arg(
+            Arg::with_name("psl-max-age")
+                .long("psl-max-age")
+                .takes_value(true)
+                .value_name("DAYS")
+                .default_value("30")
+                .display_order(4)
+                .help("How old the downloaded public suffix list may get before it is updated"),
+        )
+        .arg(
+            Arg::with_name("offline")
+                .long("offline")
+                .conflicts_with("update-psl")
+                .display_order(4)
+                .help("Never download the public suffix list, using the copy hostparser was built with"),
+        )
+        .arg(
+            Arg::with_name("private-suffixes")
+                .long("private-suffixes")
+                .takes_value(true)
+                .possible_values(["on", "off"])
+                .default_value("off")
+                .display_order(4)
+                .help("Treat private suffixes such as github.io as suffixes rather than root domains"),
+        )
+        .arg(
+            Arg::with_name("keep-trailing-dot")
+                .long("keep-trailing-dot")
+                .display_order(4)
+                .help("Print the trailing dot of fully qualified names such as example.com."),
+        )
+        .arg(
+            Arg::with_name("preserve-case")
+                .long("preserve-case")
+                .display_order(4)
+                .help("Print hosts in the case they were given in rather than lowercased"),
+        )
+        .arg(
+            Arg::with_name("validate")
+                .long("validate")
+                .display_order(4)
+                .help("Check hosts against the hostname rules and print the ones that fail with why"),
+        )
+        .arg(
+            Arg::with_name("invalid-output")
+                .long("invalid-output")
+                .takes_value(true)
+                .value_name("FILE")
+                .requires("validate")
+                .display_order(4)
+                .help("Write the hosts that fail --validate to a file rather than stderr"),
+        )
+        .arg(
+            Arg::with_name("strict")
+                .long("strict")
+                .display_order(7)
+                .help("Stop at the first host that can not be parsed"),
+        )
+        .arg(
+            Arg::with_name("stats")
+                .long("stats")
+                .display_order(7)
+                .help("Print a summary of the run to stderr when it is done"),
+        )
+        .arg(
+            Arg::with_name("no-progress")
+                .long("no-progress")
+                .display_order(7)
+                .help("Never show a progress bar, even on a terminal"),
+        )
+        .arg(
+            Arg::with_name("metrics")
+                .long("metrics")
+                .takes_value(true)
+                .value_name("ADDR")
+                .display_order(7)
+                .help("Serve Prometheus metrics at http://ADDR/metrics while the run goes on"),
+        )
+        .arg(
+            Arg::with_name("checkpoint")
+                .long("checkpoint")
+                .takes_value(true)
+                .value_name("FILE")
+                .display_order(6)
+                .help("Periodically save how far into the inputs the run has got"),
+        )
+        .arg(
+            Arg::with_name("resume")
+                .long("resume")
+                .requires("checkpoint")
+                // these only write once every input has been read
+                .conflicts_with_all(&["sort", "count", "top", "tree", "report"])
+                .display_order(6)
+                .help("Continue from the position saved in the checkpoint file"),
+        )
+        .subcommand(
+            App::new("permute")
+                .about("Print dnsgen and altdns style alterations of hosts, for resolving")
+                .arg(
+                    Arg::with_name("hosts")
+                        .multiple_values(true)
+                        .help("Hosts to alter, read instead of stdin"),
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .short('f')
+                        .long("file")
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .help("Read the hosts from a file"),
+                )
+                .arg(
+                    Arg::with_name("wordlist")
+                        .short('w')
+                        .long("wordlist")
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .help("The words to insert, one per line, instead of common environment names"),
+                ),
+);
+
+    // optional input sources
+    #[cfg(feature = "kafka")]
+    let app = app
+        .arg(
+            Arg::with_name("kafka-brokers")
+                .long("kafka-brokers")
+                .takes_value(true)
+                .requires("kafka-topic")
+                .display_order(1)
+                .help("Consume hosts from Kafka, a comma separated list of brokers"),
+        )
+        .arg(
+            Arg::with_name("kafka-topic")
+                .long("kafka-topic")
+                .takes_value(true)
+                .requires("kafka-brokers")
+                .display_order(1)
+                .help("The Kafka topic to consume hosts from"),
+        )
+        .arg(
+            Arg::with_name("kafka-group")
+                .long("kafka-group")
+                .takes_value(true)
+                .default_value("hostparser")
+                .display_order(1)
+                .help("The Kafka consumer group"),
+        );
+
+    let matches = app.get_matches();
+
+    if matches.is_present("silent") {
+        log::set_level(log::QUIET);
+    } else {
+        log::set_level(log::WARN + matches.occurrences_of("verbose").min(2) as u8);
+    }
+
+    if let Some(sub) = matches.subcommand_matches("permute") {
+        let extractor = extractor(&matches).await;
+        if let Err(e) = permute::run(sub, &extractor) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        std::process::exit(0);
+    }
+
+    if matches.is_present("update-psl") {
+        match psl::update().await {
+            Ok(path) => {
+                eprintln!("saved the public suffix list to {}", path.display());
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
 
     let rate = match matches.value_of("rate").unwrap().parse::<u32>() {
         Ok(n) => n,
         Err(_) => {
-            println!("{}", "could not parse rate, using default of 1000");
+            warn!("could not parse rate, using default of 1000");
             1000
         }
     };
@@ -64,7 +1110,7 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
     let concurrency = match matches.value_of("concurrency").unwrap().parse::<u32>() {
         Ok(n) => n,
         Err(_) => {
-            println!("{}", "could not parse concurrency, using default of 100");
+            warn!("could not parse concurrency, using default of 100");
             100
         }
     };
@@ -72,11 +1118,165 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
     let w: usize = match matches.value_of("workers").unwrap().parse::<usize>() {
         Ok(w) => w,
         Err(_) => {
-            println!("{}", "could not parse workers, using default of 1");
+            warn!("could not parse workers, using default of 1");
             1
         }
     };
 
+    // read from the given hosts, files, directories, urls, buckets, captures
+    // and databases, falling back to stdin
+    let mut sources = Vec::new();
+    if let Some(hosts) = matches.values_of("hosts") {
+        sources.push(Source::Hosts(hosts.map(|host| host.to_string()).collect()));
+    }
+    if let Some(files) = matches.values_of("file") {
+        match input::file_sources(&files.collect::<Vec<_>>()) {
+            Ok(found) if matches.is_present("follow") => {
+                sources.push(input::follow_source(found));
+            }
+            Ok(found) => sources.extend(found),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(dirs) = matches.values_of("dir") {
+        match input::dir_sources(&dirs.collect::<Vec<_>>()) {
+            Ok(found) => sources.extend(found),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(urls) = matches.values_of("url") {
+        sources.extend(urls.map(|url| Source::Url(url.to_string())));
+    }
+    if let Some(locations) = matches.values_of("s3") {
+        sources.extend(locations.map(|location| Source::S3(location.to_string())));
+    }
+    if let Some(captures) = matches.values_of("pcap") {
+        match input::pcap_sources(&captures.collect::<Vec<_>>()) {
+            Ok(found) => sources.extend(found),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(path) = matches.value_of("sqlite") {
+        if let Err(e) = std::fs::metadata(path) {
+            eprintln!("could not open database {}: {}", path, e);
+            std::process::exit(1);
+        }
+        sources.push(Source::Sqlite {
+            path: path.into(),
+            query: matches.value_of("query").unwrap().to_string(),
+        });
+    }
+    if let Some(url) = matches.value_of("redis") {
+        sources.push(Source::Redis {
+            url: url.to_string(),
+            key: matches.value_of("key").unwrap().to_string(),
+            group: matches.value_of("redis-group").unwrap().to_string(),
+        });
+    }
+    #[cfg(feature = "kafka")]
+    if let Some(brokers) = matches.value_of("kafka-brokers") {
+        sources.push(Source::Kafka {
+            brokers: brokers.to_string(),
+            topic: matches.value_of("kafka-topic").unwrap().to_string(),
+            group: matches.value_of("kafka-group").unwrap().to_string(),
+        });
+    }
+    if let Some(url) = matches.value_of("certstream") {
+        sources.push(Source::CertStream(url.to_string()));
+    }
+    let inputs = [
+        "file",
+        "dir",
+        "url",
+        "s3",
+        "pcap",
+        "sqlite",
+        "redis",
+        "kafka-brokers",
+        "certstream",
+        "listen-unix",
+        "serve",
+    ];
+    let piped = !std::io::stdin().is_terminal();
+    if !inputs
+        .iter()
+        .any(|name| matches.try_contains_id(name).unwrap_or(false))
+        && (piped || !matches.is_present("hosts"))
+    {
+        sources.push(Source::Stdin);
+    }
+
+    let input_format = match matches.is_present("grep") {
+        true => "grep",
+        false => matches.value_of("input-format").unwrap(),
+    };
+    // without a header a named column is nowhere to be found
+    if matches.is_present("no-header")
+        && matches
+            .value_of("column")
+            .unwrap()
+            .parse::<usize>()
+            .is_err()
+    {
+        eprintln!("--no-header needs --column to be a zero based index");
+        std::process::exit(1);
+    }
+    let format = InputFormat::new(
+        input_format,
+        &FormatOptions {
+            field: matches.value_of("field").unwrap().to_string(),
+            column: matches.value_of("column").unwrap().to_string(),
+            header: !matches.is_present("no-header"),
+            ips: matches.is_present("masscan-ips"),
+            sources: matches.is_present("amass-sources"),
+            mailbox: matches.is_present("keep-mailbox"),
+            comments: !matches.is_present("no-comments"),
+        },
+    );
+    let encoding = match InputEncoding::new(matches.value_of("encoding").unwrap()) {
+        Ok(encoding) => encoding,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // pick up where an earlier run stopped, as long as the inputs are the same
+    let resume = if matches.is_present("resume") {
+        let path = matches.value_of("checkpoint").unwrap();
+        match checkpoint::load(path.as_ref()) {
+            Ok(position)
+                if sources
+                    .get(position.source)
+                    .is_some_and(|source| source.to_string() == position.name) =>
+            {
+                Some(position)
+            }
+            Ok(position) => {
+                eprintln!(
+                    "checkpoint {} was saved while reading {}, which is not one of the inputs",
+                    path, position.name
+                );
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
     // Set up a worker pool with the number of threads specified from the arguments
     let rt = Builder::new_multi_thread()
         .enable_all()
@@ -87,74 +1287,1083 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
     // job channels
     let (job_tx, job_rx) = spmc::channel::<Job>();
 
-    rt.spawn(async move { send_url(job_tx, rate).await });
+    let delimiter = match matches.value_of("delimiter") {
+        Some(delimiter) if delimiter.len() == 1 => Some(delimiter.as_bytes()[0]),
+        Some(_) => {
+            eprintln!("--delimiter must be a single character");
+            std::process::exit(1);
+        }
+        None => None,
+    };
+    let key = if matches.is_present("suffix-only") {
+        Field::Suffix
+    } else if matches.is_present("subdomain-only") {
+        Field::Subdomain
+    } else {
+        Field::Root
+    };
+    // the scores only have columns of their own in text
+    if matches.value_of("entropy") == Some("tag") && matches.occurrences_of("output-format") > 0 {
+        eprintln!("--entropy tag cannot be used with --output-format, use --format with {{entropy}}, {{ngram}} and {{dga}}");
+        std::process::exit(1);
+    }
+    let template = if key == Field::Suffix {
+        Some(Template::new("{suffix}"))
+    } else if key == Field::Subdomain {
+        Some(Template::new("{subdomain}"))
+    } else if matches.is_present("with-input") {
+        Some(Template::new("{input}\t{root}"))
+    } else if matches.is_present("depth") {
+        Some(Template::new("{host}\t{depth}"))
+    } else if matches.is_present("reverse-notation") {
+        Some(Template::new("{reversed}"))
+    } else if matches.is_present("surt") {
+        Some(Template::new("{surt}"))
+    } else if let Some(template) = matches.value_of("format") {
+        Some(Template::new(template))
+    } else if let Some(fields) = matches.value_of("fields") {
+        Some(Template::from_fields(
+            fields,
+            matches.value_of("separator").unwrap(),
+        ))
+    } else if matches.is_present("typosquat") {
+        Some(Template::new("{root}\t{squat}"))
+    } else if matches.value_of("entropy") == Some("tag") {
+        Some(Template::new("{host}\t{entropy}\t{ngram}\t{dga}"))
+    } else {
+        None
+    };
+    let output_format = match template {
+        Some(Ok(template)) => OutputFormat::Template(template),
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        None => OutputFormat::new(matches.value_of("output-format").unwrap(), delimiter),
+    };
+    if (matches.is_present("append") || matches.is_present("resume"))
+        && matches!(
+            output_format,
+            OutputFormat::Json | OutputFormat::Xml | OutputFormat::Dot
+        )
+    {
+        eprintln!(
+            "--append and --resume cannot add to a json array, xml document or graph, use --output-format jsonl"
+        );
+        std::process::exit(1);
+    }
+    let top = match matches.value_of("top").map(|top| top.parse::<usize>()) {
+        Some(Ok(top)) => Some(top),
+        Some(Err(_)) => {
+            eprintln!("--top must be a number");
+            std::process::exit(1);
+        }
+        None => None,
+    };
+    let unique_approx = match matches.value_of("unique-approx") {
+        Some(items) => match (
+            items.parse::<u64>(),
+            matches.value_of("fp-rate").unwrap().parse::<f64>(),
+        ) {
+            (Ok(items), Ok(rate)) if rate > 0.0 && rate < 1.0 => Some((items, rate)),
+            (Err(_), _) => {
+                eprintln!("--unique-approx must be a number");
+                std::process::exit(1);
+            }
+            _ => {
+                eprintln!("--fp-rate must be between 0 and 1");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let output = match Output::new(&OutputOptions {
+        paths: matches
+            .values_of("output")
+            .map(|paths| paths.map(|path| path.to_string()).collect())
+            .unwrap_or_default(),
+        split: matches.value_of("split-by").map(|by| {
+            let field = match by {
+                "suffix" => Field::Suffix,
+                _ => Field::Root,
+            };
+            (field, matches.value_of("output-dir").unwrap().to_string())
+        }),
+        webhooks: matches
+            .values_of("webhook")
+            .map(|urls| urls.map(|url| url.to_string()).collect())
+            .unwrap_or_default(),
+        syslogs: matches
+            .values_of("syslog")
+            .map(|urls| urls.map(|url| url.to_string()).collect())
+            .unwrap_or_default(),
+        elastic: matches.value_of("elastic").map(|url| {
+            (
+                url.to_string(),
+                matches.value_of("index").unwrap().to_string(),
+            )
+        }),
+        // a resumed run carries on with the output of the one it resumes
+        append: matches.is_present("append") || matches.is_present("resume"),
+        in_place: matches.is_present("checkpoint"),
+        compression: matches.value_of("compress").and_then(Compression::new),
+        format: output_format,
+        key,
+        color: !matches.is_present("no-color") && std::env::var_os("NO_COLOR").is_none(),
+        print0: matches.is_present("print0"),
+        labels: matches.is_present("split-labels"),
+        unique: matches.is_present("unique"),
+        ordered: matches.is_present("ordered"),
+        unique_approx,
+        sort: match matches.value_of("sort") {
+            Some("suffix") => Some(SortOrder::Suffix),
+            Some(_) => Some(SortOrder::Root),
+            None => None,
+        },
+        count: matches.is_present("count") || top.is_some(),
+        top,
+        tree: matches.is_present("tree"),
+        report: matches.is_present("report"),
+        ips: matches
+            .value_of("ip-output")
+            .filter(|_| matches.value_of("ips") == Some("separate"))
+            .map(|path| path.to_string()),
+        unknown: matches
+            .value_of("unknown-output")
+            .filter(|_| matches.value_of("unknown-tld") == Some("separate-file"))
+            .map(|path| path.to_string()),
+    }) {
+        Ok(output) => Arc::new(output),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Some(position) = &resume {
+        output.remember(&position.seen);
+    }
+
+    // how many jobs the workers have finished, for checkpointing
+    let done = Arc::new(AtomicU64::new(0));
+    // the summary is also part of verbose output
+    let report = matches.is_present("stats") || log::enabled(log::INFO);
+    let metrics = matches.is_present("metrics") || matches.is_present("serve");
+    let stats = Arc::new(Stats::new(concurrency as usize, report || metrics));
+    if let Some(addr) = matches.value_of("metrics") {
+        match server::http::bind(addr) {
+            Ok(listener) => {
+                info!("serving metrics on http://{}/metrics", addr);
+                rt.spawn(server::metrics::serve(listener, stats.clone()));
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let mut progress = None;
+    if matches.is_present("listen-unix") || matches.is_present("serve") {
+        if !sources.is_empty() {
+            eprintln!("--listen-unix and --serve can not be combined with other inputs");
+            std::process::exit(1);
+        }
+        let unix = match matches.value_of("listen-unix") {
+            Some(path) => match server::unix::bind(path.as_ref()).await {
+                Ok(listener) => Some(listener),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            },
+            None => None,
+        };
+        let max_body = match matches.value_of("max-body").unwrap().parse::<usize>() {
+            Ok(max_body) => max_body,
+            Err(_) => {
+                eprintln!("--max-body must be a number of bytes");
+                std::process::exit(1);
+            }
+        };
+        let http = match matches.value_of("serve") {
+            Some(addr) => match server::http::bind(addr) {
+                Ok(listener) => Some(listener),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            },
+            None => None,
+        };
+        let unix_path = matches
+            .value_of("listen-unix")
+            .unwrap_or_default()
+            .to_string();
+        let http_addr = matches.value_of("serve").unwrap_or_default().to_string();
+        let stats = stats.clone();
+        rt.spawn(async move {
+            let tx = server::forward(job_tx, rate, stats.clone());
+            if let Some(listener) = unix {
+                info!("listening on {}", unix_path);
+                tokio::spawn(server::unix::serve(listener, tx.clone(), format.clone()));
+            }
+            if let Some(listener) = http {
+                info!("listening on http://{}", http_addr);
+                tokio::spawn(server::http::serve(listener, tx, format, stats, max_body));
+            }
+        });
+    } else {
+        let checkpoint = matches.value_of("checkpoint").map(|path| {
+            Checkpointer::new(path.into(), done.clone(), output.clone(), resume.is_some())
+        });
+        // the bar needs to know how much there is to read
+        let total: Option<u64> = sources.iter().map(|source| source.size()).sum();
+        if let Some(total) = total.filter(|_| {
+            !sources.is_empty()
+                && !matches.is_present("no-progress")
+                && log::enabled(log::WARN)
+                && std::io::stderr().is_terminal()
+        }) {
+            progress = Some(Progress::start(stats.clone(), total));
+        }
+        let cidr_limit = match matches.value_of("cidr-limit").unwrap().parse::<usize>() {
+            Ok(limit) => Some(limit).filter(|_| matches.is_present("expand-cidr")),
+            Err(_) => {
+                eprintln!("--cidr-limit must be a number");
+                std::process::exit(1);
+            }
+        };
+        let queue = Queue::new(
+            job_tx,
+            rate,
+            checkpoint,
+            stats.clone(),
+            cidr_limit,
+            matches.is_present("nested-urls"),
+            matches.is_present("ordered"),
+        );
+        rt.spawn(async move { send_url(queue, sources, format, encoding, resume).await });
+    }
 
     // process the jobs
     let workers = FuturesUnordered::new();
 
     // process the jobs for scanning.
-    for _ in 0..concurrency {
+    let level = match matches
+        .value_of("level")
+        .map(|level| level.parse::<usize>())
+    {
+        Some(Ok(level)) if level >= 2 => Some(level),
+        Some(_) => {
+            eprintln!("--level must be a number of labels, at least 2");
+            std::process::exit(1);
+        }
+        None => None,
+    };
+    let mut prefixes: Vec<String> = matches
+        .values_of("strip-prefix")
+        .into_iter()
+        .flatten()
+        .flat_map(|labels| labels.split(','))
+        .map(|label| label.trim().trim_end_matches('.').to_lowercase())
+        .filter(|label| !label.is_empty())
+        .collect();
+    if matches.is_present("strip-www") {
+        prefixes.push("www".to_string());
+    }
+    let extractor = extractor(&matches).await;
+    let options = Arc::new(ParseOptions {
+        extractor,
+        strict: matches.is_present("strict"),
+        ports: Ports::new(matches.value_of("ports").unwrap()),
+        ips: Ips::new(matches.value_of("ips").unwrap()),
+        unknown: Unknown::new(matches.value_of("unknown-tld").unwrap()),
+        idn: matches.value_of("idn").map(Idn::new),
+        form: matches.value_of("unicode-normalization").map(Form::new),
+        wildcards: matches.is_present("tag-wildcards"),
+        no_extract: matches.is_present("no-extract"),
+        trailing_dot: matches.is_present("keep-trailing-dot"),
+        preserve_case: matches.is_present("preserve-case"),
+        validate: matches.is_present("validate"),
+        level,
+        expand: matches.is_present("expand"),
+        prefixes,
+        grep: matches!(input_format, "grep" | "js"),
+        pattern: match matches.value_of("match").map(regex::Regex::new) {
+            Some(Ok(pattern)) => Some((pattern, matches.is_present("match-host"))),
+            Some(Err(e)) => {
+                eprintln!("invalid --match pattern: {}", e);
+                std::process::exit(1);
+            }
+            None => None,
+        },
+        seeds: Seeds::new(
+            matches
+                .values_of("typosquat")
+                .into_iter()
+                .flatten()
+                .flat_map(|seeds| seeds.split(',')),
+        ),
+        entropy: match matches.value_of("entropy") {
+            Some(entropy) => match matches
+                .value_of("entropy-threshold")
+                .unwrap()
+                .parse::<f64>()
+            {
+                Ok(threshold) => Some((Entropy::new(entropy), threshold)),
+                Err(_) => {
+                    eprintln!("--entropy-threshold must be a number of bits");
+                    std::process::exit(1);
+                }
+            },
+            None => None,
+        },
+        invalid: match matches.is_present("validate") {
+            true => match Invalid::open(matches.value_of("invalid-output")) {
+                Ok(invalid) => Some(invalid),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            },
+            false => None,
+        },
+    });
+    for worker in 0..concurrency as usize {
         let jrx = job_rx.clone();
+        let done = done.clone();
+        let output = output.clone();
+        let stats = stats.clone();
+        let options = options.clone();
         workers.push(task::spawn(async move {
             //  run the detector
-            run_parser(jrx).await
+            run_parser(jrx, output, done, stats, worker, options).await
         }));
     }
+    // inputs such as --follow only end when the run is stopped, and what
+    // was written still has to be put in place
+    {
+        let output = output.clone();
+        let stats = stats.clone();
+        // the workers may be blocking every thread of this runtime
+        rt.spawn(async move {
+            stopped().await;
+            output.finish();
+            if report {
+                stats.report();
+            }
+            std::process::exit(stats::EXIT_STOPPED);
+        });
+    }
     let _: Vec<_> = workers.collect().await;
+    if let Some(progress) = progress.as_mut() {
+        progress.finish();
+    }
     rt.shutdown_background();
+    output.finish();
+    if report {
+        stats.report();
+    }
 
-    Ok(())
+    // the run finished, so there is nothing left to resume
+    if let Some(path) = matches.value_of("checkpoint") {
+        checkpoint::remove(path.as_ref());
+    }
+
+    std::process::exit(stats.exit_code());
+}
+
+/// Builds the extractor from the suffix list the arguments ask for: a
+/// `--psl` file, the cached list or the one hostparser was built with, along
+/// with any extra suffixes.
+async fn extractor(matches: &ArgMatches) -> TldExtractor {
+    let private = matches.value_of("private-suffixes") == Some("on");
+    let offline = matches.is_present("offline");
+    let max_age = match matches.value_of("psl-max-age").unwrap().parse::<u64>() {
+        Ok(days) => Duration::from_secs(days * 86400),
+        Err(_) => {
+            eprintln!("--psl-max-age must be a number of days");
+            std::process::exit(1);
+        }
+    };
+    let list = match matches.value_of("psl") {
+        Some(path) => Some(psl::read(path)),
+        None => psl::cached(max_age, offline).await.map(Ok),
+    };
+    let mut extra: Vec<String> = matches
+        .values_of("extra-suffix")
+        .into_iter()
+        .flatten()
+        .flat_map(|suffixes| suffixes.split(','))
+        .map(|suffix| suffix.trim().trim_matches('.').to_lowercase())
+        .filter(|suffix| !suffix.is_empty())
+        .collect();
+    if matches.is_present("internal-suffixes") {
+        extra.extend(psl::INTERNAL.iter().map(|suffix| suffix.to_string()));
+    }
+    let mut rules = match list {
+        Some(Ok(list)) => psl::parse(&list, private),
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        None => psl::parse(psl::EMBEDDED, private),
+    };
+    rules.extend(extra);
+    match psl::extractor(&rules) {
+        Ok(extractor) => extractor,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Waits for Ctrl-C or, on unix, SIGTERM.
+async fn stopped() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut term) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = term.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Hands hosts to the workers at the configured rate, keeping the
+/// checkpoint up to date as it goes.
+struct Queue {
+    tx: spmc::Sender<Job>,
+    lim: RateLimiter<NotKeyed, InMemoryState, DefaultClock>,
+    checkpoint: Option<Checkpointer>,
+    stats: Arc<Stats>,
+    seq: u64,
+    /// How many addresses a network is expanded to, when it is.
+    cidr_limit: Option<usize>,
+    /// Whether the urls in the query strings of urls are sent as well.
+    nested: bool,
+    /// Whether lines without hosts still take up a line of the output.
+    ordered: bool,
+}
+
+impl Queue {
+    fn new(
+        tx: spmc::Sender<Job>,
+        rate: u32,
+        checkpoint: Option<Checkpointer>,
+        stats: Arc<Stats>,
+        cidr_limit: Option<usize>,
+        nested: bool,
+        ordered: bool,
+    ) -> Queue {
+        //set rate limit
+        let lim = RateLimiter::direct(Quota::per_second(std::num::NonZeroU32::new(rate).unwrap()));
+        Queue {
+            tx,
+            lim,
+            checkpoint,
+            stats,
+            seq: 0,
+            cidr_limit,
+            nested,
+            ordered,
+        }
+    }
+
+    /// Sends the hosts of a line read from `source` up to `offset`. In
+    /// ordered output a blank or comment line is sent without a host, so
+    /// the lines of the output stay in step with those of the input.
+    async fn send_line(&mut self, hosts: Vec<Host>, index: usize, source: &Source, offset: u64) {
+        if hosts.is_empty() && self.ordered {
+            let msg = Job {
+                host: None,
+                sources: vec![],
+                mailbox: None,
+                reply: None,
+                seq: self.seq,
+            };
+            self.seq += 1;
+            let _ = self.tx.send(msg);
+            if let Some(checkpoint) = self.checkpoint.as_mut() {
+                checkpoint.sent(1);
+            }
+        }
+        self.send(hosts, index, source, offset).await;
+    }
+
+    /// Sends the hosts read from `source` up to `offset`.
+    async fn send(&mut self, hosts: Vec<Host>, index: usize, source: &Source, offset: u64) {
+        let hosts = match self.cidr_limit {
+            Some(limit) => expand(hosts, limit),
+            None => hosts,
+        };
+        let hosts = match self.nested {
+            true => nest(hosts),
+            false => hosts,
+        };
+        let jobs = hosts.len() as u64;
+        for host in hosts {
+            self.lim.until_ready().await;
+            let msg = Job {
+                host: Some(host.name),
+                sources: host.sources,
+                mailbox: host.mailbox,
+                reply: None,
+                seq: self.seq,
+            };
+            self.seq += 1;
+            if self.tx.send(msg).is_err() {
+                continue;
+            }
+            self.stats.hosts.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Some(checkpoint) = self.checkpoint.as_mut() {
+            checkpoint.sent(jobs);
+            checkpoint.mark(index, source, offset);
+        }
+    }
+}
+
+/// Adds the urls found in the query strings of the hosts after each of them.
+fn nest(hosts: Vec<Host>) -> Vec<Host> {
+    let mut nested = Vec::with_capacity(hosts.len());
+    for host in hosts {
+        let urls = normalize::nested(&host.name);
+        nested.push(host.clone());
+        nested.extend(urls.into_iter().map(|url| Host {
+            name: url,
+            ..host.clone()
+        }));
+    }
+    nested
 }
 
+/// Replaces the networks among the hosts with their addresses.
+fn expand(hosts: Vec<Host>, limit: usize) -> Vec<Host> {
+    let mut expanded = Vec::with_capacity(hosts.len());
+    for host in hosts {
+        match normalize::expand(&host.name, limit) {
+            Some((addresses, more)) => {
+                if more {
+                    warn!(
+                        "only expanding the first {} addresses of {}",
+                        limit, host.name
+                    );
+                }
+                expanded.extend(addresses.into_iter().map(|address| Host {
+                    name: address,
+                    ..host.clone()
+                }));
+            }
+            None => expanded.push(host),
+        }
+    }
+    expanded
+}
+
+/// Fetches the documents a document links to, such as the sitemaps of a
+/// sitemap index, and sends their hosts, following their own links in turn
+/// up to `MAX_LINKED` documents.
+async fn send_linked(
+    queue: &mut Queue,
+    parser: &mut InputFormat,
+    mut pending: Vec<String>,
+    index: usize,
+    source: &Source,
+    offset: u64,
+) {
+    let mut fetched = std::collections::HashSet::new();
+    while let Some(url) = pending.pop() {
+        if !fetched.insert(url.clone()) {
+            continue;
+        }
+        if fetched.len() > MAX_LINKED {
+            warn!(
+                "only following the first {} documents of {}",
+                MAX_LINKED, source
+            );
+            return;
+        }
+        info!("reading {}", url);
+        let mut data = Vec::new();
+        let read = match Source::Url(url.clone()).open().await {
+            Ok(mut reader) => reader.read_to_end(&mut data).await.map_err(|e| e.into()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = read {
+            warn!("error reading {}: {}", url, e);
+            queue.stats.errors.fetch_add(1, Ordering::Relaxed);
+            continue;
+        }
+        queue
+            .stats
+            .bytes
+            .fetch_add(data.len() as u64, Ordering::Relaxed);
+        let hosts = parser.document_hosts(&data);
+        queue.send(hosts, index, source, offset).await;
+        pending.extend(parser.linked_documents(&data));
+    }
+}
+
+/// How many linked documents are followed from a single source.
+const MAX_LINKED: usize = 1000;
+
 async fn send_url(
-    mut tx: spmc::Sender<Job>,
-    rate: u32,
+    mut queue: Queue,
+    sources: Vec<Source>,
+    format: InputFormat,
+    encoding: InputEncoding,
+    resume: Option<Position>,
 ) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
-    //set rate limit
-    let lim = RateLimiter::direct(Quota::per_second(std::num::NonZeroU32::new(rate).unwrap()));
+    for (index, source) in sources.iter().enumerate() {
+        // sources finished by the run being resumed are skipped entirely
+        let skip = match &resume {
+            Some(position) if index < position.source => {
+                let size = source.size().unwrap_or_default();
+                queue.stats.bytes.fetch_add(size, Ordering::Relaxed);
+                continue;
+            }
+            Some(position) if index == position.source => position.offset,
+            _ => 0,
+        };
+        queue.stats.bytes.fetch_add(skip, Ordering::Relaxed);
+        if skip > 0 {
+            info!("resuming {} at byte {}", source, skip);
+        } else {
+            info!("reading {}", source);
+        }
+        let mut parser = format.clone();
 
-    let stdin = io::BufReader::new(io::stdin());
-    let mut lines = stdin.lines();
+        // plain files are split into lines in place, straight from the page cache
+        if !parser.is_document() {
+            if let Some(map) = source
+                .map()
+                .filter(|map| encoding.detect(&map[..map.len().min(4096)]).is_none())
+            {
+                let mut offset = skip.min(map.len() as u64) as usize;
+                for line in map[offset..].split_inclusive(|b| *b == b'\n') {
+                    offset += line.len();
+                    queue.stats.lines.fetch_add(1, Ordering::Relaxed);
+                    queue
+                        .stats
+                        .bytes
+                        .fetch_add(line.len() as u64, Ordering::Relaxed);
+                    let line = input::encoding::decode_line(line);
+                    let hosts = parser.hosts(line.trim_end_matches(['\n', '\r']));
+                    queue.send_line(hosts, index, source, offset as u64).await;
+                }
+                continue;
+            }
+        }
 
-    // send the jobs
-    while let Some(line) = lines.next().await {
-        let host = line.unwrap();
-        lim.until_ready().await;
-        let msg = Job {
-            host: Some(host.to_string().clone()),
+        let reader = match source.open().await {
+            Ok(reader) => reader,
+            Err(e) => {
+                warn!("{}", e);
+                queue.stats.errors.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
         };
-        if let Err(_) = tx.send(msg) {
+        let transcoded = if parser.is_binary() {
+            Ok(reader)
+        } else {
+            input::encoding::transcode(reader, encoding).await
+        };
+        let mut reader = match transcoded {
+            Ok(reader) => reader,
+            Err(e) => {
+                warn!("error reading {}: {}", source, e);
+                queue.stats.errors.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+        };
+
+        // formats such as xml need the whole document before any host can be read
+        if parser.is_document() {
+            if skip > 0 {
+                continue;
+            }
+            let mut data = Vec::new();
+            if let Err(e) = reader.read_to_end(&mut data).await {
+                warn!("error reading {}: {}", source, e);
+                queue.stats.errors.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            let lines = data.iter().filter(|b| **b == b'\n').count() as u64;
+            queue.stats.lines.fetch_add(lines, Ordering::Relaxed);
+            queue
+                .stats
+                .bytes
+                .fetch_add(data.len() as u64, Ordering::Relaxed);
+            let hosts = parser.document_hosts(&data);
+            queue.send(hosts, index, source, data.len() as u64).await;
+            let linked = parser.linked_documents(&data);
+            if !linked.is_empty() {
+                send_linked(
+                    &mut queue,
+                    &mut parser,
+                    linked,
+                    index,
+                    source,
+                    data.len() as u64,
+                )
+                .await;
+            }
             continue;
         }
+
+        let mut offset = 0;
+        if skip > 0 {
+            match io::copy(&mut (&mut reader).take(skip), &mut io::sink()).await {
+                Ok(n) => offset = n,
+                Err(e) => {
+                    warn!("error reading {}: {}", source, e);
+                    queue.stats.errors.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
+            }
+        }
+
+        // send the jobs
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line).await {
+                Ok(0) => break,
+                Ok(n) => offset += n as u64,
+                Err(e) => {
+                    warn!("error reading {}: {}", source, e);
+                    queue.stats.errors.fetch_add(1, Ordering::Relaxed);
+                    break;
+                }
+            }
+            queue.stats.lines.fetch_add(1, Ordering::Relaxed);
+            queue
+                .stats
+                .bytes
+                .fetch_add(line.len() as u64, Ordering::Relaxed);
+            let text = input::encoding::decode_line(&line);
+            let hosts = parser.hosts(text.trim_end_matches(['\n', '\r']));
+            queue.send_line(hosts, index, source, offset).await;
+        }
     }
     Ok(())
 }
 
-pub async fn run_parser(rx: spmc::Receiver<Job>) {
-    while let Ok(job) = rx.recv() {
-        let job_host = job.host.unwrap();
-        let ext: TldExtractor = TldOption::default().build();
-        let extractor = match ext.extract(&job_host) {
-            Ok(extractor) => extractor,
-            Err(_) => continue,
+/// How the workers parse hosts.
+pub struct ParseOptions {
+    /// Splits hosts up along the public suffixes.
+    pub extractor: TldExtractor,
+    /// Whether the first host that can not be parsed stops the run.
+    pub strict: bool,
+    /// Whether the port of a host is dropped, kept or required.
+    pub ports: Ports,
+    /// Whether ip addresses are left out, printed or set apart.
+    pub ips: Ips,
+    /// What happens to hosts whose suffix is not on the public suffix list.
+    pub unknown: Unknown,
+    /// How internationalized names are printed, as extracted when not set.
+    pub idn: Option<Idn>,
+    /// The Unicode normalization form hosts are brought into first.
+    pub form: Option<Form>,
+    /// Whether results of wildcard hosts are marked as such.
+    pub wildcards: bool,
+    /// Whether hosts are only cleaned up and printed whole.
+    pub no_extract: bool,
+    /// Whether the trailing dot of a fully qualified name is printed.
+    pub trailing_dot: bool,
+    /// Whether hosts are printed in the case they were given in.
+    pub preserve_case: bool,
+    /// Whether hosts are checked against the rules for hostnames.
+    pub validate: bool,
+    /// How many labels the printed domain has, counting the suffix as one.
+    pub level: Option<usize>,
+    /// Whether every parent of a host is printed rather than only its root.
+    pub expand: bool,
+    /// The labels stripped off the front of hosts, such as `www`.
+    pub prefixes: Vec<String>,
+    /// Whether the hosts were picked out of free text or code, so the ones
+    /// without a known suffix are skipped rather than failed.
+    pub grep: bool,
+    /// The pattern the printed hosts have to match, and whether it is
+    /// matched against the whole host rather than the root domain.
+    pub pattern: Option<(regex::Regex, bool)>,
+    /// What to do with hosts that look machine generated, and how many bits
+    /// of entropy make one look so.
+    pub entropy: Option<(Entropy, f64)>,
+    /// The apex domains whose lookalikes are the only hosts printed.
+    pub seeds: Seeds,
+    /// Where the hosts that could not be parsed are written, with why.
+    pub invalid: Option<Invalid>,
+}
+
+/// Lines that could not be parsed, written to stderr or a file.
+pub struct Invalid {
+    writer: std::sync::Mutex<Box<dyn std::io::Write + Send>>,
+}
+
+impl Invalid {
+    /// Writes to the file at `path`, or to stderr without one.
+    pub fn open(path: Option<&str>) -> Result<Invalid, Box<dyn Error + Send + Sync + 'static>> {
+        let writer: Box<dyn std::io::Write + Send> = match path {
+            // a line at a time, as nothing flushes the file at the end
+            Some(path) => match std::fs::File::create(path) {
+                Ok(file) => Box::new(std::io::LineWriter::new(file)),
+                Err(e) => return Err(format!("could not create {}: {}", path, e).into()),
+            },
+            None => Box::new(std::io::stderr()),
         };
+        Ok(Invalid {
+            writer: std::sync::Mutex::new(writer),
+        })
+    }
 
-        let mut root_domain = String::from("");
+    fn write(&self, host: &str, reason: &str) {
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(e) = writeln!(writer, "{}\t{}", host, reason) {
+            warn!("could not write invalid host {}: {}", host, e);
+        }
+    }
+}
 
-        let domain = match extractor.domain {
-            Some(domain) => domain,
-            None => continue,
+/// Parses jobs until the queue closes, counting them as the `worker`th
+/// worker.
+pub async fn run_parser(
+    rx: spmc::Receiver<Job>,
+    output: Arc<Output>,
+    done: Arc<AtomicU64>,
+    stats: Arc<Stats>,
+    worker: usize,
+    options: Arc<ParseOptions>,
+) {
+    while let Ok(job) = rx.recv() {
+        // a line without hosts only holds its place in ordered output
+        if job.host.is_none() {
+            output.write(job.seq, None);
+            done.fetch_add(1, Ordering::Relaxed);
+            continue;
+        }
+        let result = match parse_job(&job, &options) {
+            Ok(Some(result)) => Some(result),
+            Ok(None) => {
+                stats.skip(worker);
+                None
+            }
+            // words that only look like hosts are not worth a mention
+            Err(_) if options.grep => {
+                stats.skip(worker);
+                None
+            }
+            Err(e) if options.strict => {
+                eprintln!("could not parse {}: {}", job.host.unwrap_or_default(), e);
+                output.finish();
+                std::process::exit(stats::EXIT_FAILURES);
+            }
+            Err(e) => {
+                let host = job.host.as_deref().unwrap_or_default();
+                match &options.invalid {
+                    Some(invalid) => invalid.write(host, &e),
+                    None => debug!("could not parse {}: {}", host, e),
+                }
+                stats.job(worker, None);
+                None
+            }
+        };
+        let parsed = result.is_some();
+        let result = result.filter(|result| options.ports != Ports::Only || result.port.is_some());
+        let result = match &options.pattern {
+            Some((pattern, true)) => result.filter(|result| pattern.is_match(&result.host())),
+            Some((pattern, false)) => result.filter(|result| pattern.is_match(&result.root())),
+            None => result,
+        };
+        let result = match (result, options.entropy) {
+            (Some(mut result), Some((entropy, threshold))) => {
+                result.dga = result.score().is_dga(threshold);
+                match entropy {
+                    Entropy::Drop if result.dga => None,
+                    Entropy::Only if !result.dga => None,
+                    _ => Some(result),
+                }
+            }
+            (result, _) => result,
+        };
+        // only the lookalikes of the seeds are of interest
+        let result = match result {
+            Some(mut result) if !options.seeds.is_empty() => {
+                result.squat = options.seeds.check(&result.domain, &result.suffix);
+                result.squat.is_some().then_some(result)
+            }
+            result => result,
+        };
+        // hosts the filters leave out count as skipped
+        match &result {
+            Some(result) => stats.job(worker, Some(result.root())),
+            None if parsed => stats.skip(worker),
+            None => {}
+        }
+        let results = match result {
+            Some(result) if options.expand => result.ancestors(),
+            Some(result) => vec![result],
+            None => vec![],
         };
+        // jobs from a connected client are answered on its connection
+        match &job.reply {
+            Some(reply) => {
+                for result in &results {
+                    let _ = reply.try_send(output::text(result));
+                }
+            }
+            None if results.is_empty() => output.write(job.seq, None),
+            None => {
+                for result in &results {
+                    output.write(job.seq, Some(result));
+                }
+            }
+        }
+        done.fetch_add(1, Ordering::Relaxed);
+    }
+}
 
-        let suffix = match extractor.suffix {
-            Some(suffix) => suffix,
-            None => continue,
+/// Extracts the parts of a job's host, or `None` when the host is left out.
+/// Fails with the reason the host could not be parsed.
+fn parse_job(job: &Job, options: &ParseOptions) -> Result<Option<JobResult>, String> {
+    let job_host = job.host.clone().unwrap();
+    let tidy = normalize::tidy(&job_host, !options.preserve_case);
+    let (host, port) = normalize::host(&tidy);
+    let host = normalize::decode(host);
+    let host = match options.form {
+        Some(form) => Cow::Owned(form.apply(&host).into_owned()),
+        None => host,
+    };
+    let port = port
+        .filter(|_| options.ports != Ports::Strip)
+        .map(|port| port.to_string());
+    let (host, wildcard) = normalize::wildcard(&host);
+    let wildcard = wildcard && options.wildcards;
+    let (host, fqdn) = normalize::trailing_dot(host);
+    let fqdn = fqdn && options.trailing_dot;
+    if options.validate && normalize::ip(host).is_none() {
+        normalize::validate(host)?;
+    }
+    if let Some(ip) = normalize::ip(host) {
+        if options.ips == Ips::Drop {
+            return Ok(None);
+        }
+        return Ok(Some(JobResult {
+            input: job_host.clone(),
+            subdomain: String::new(),
+            domain: ip.to_string(),
+            suffix: String::new(),
+            sources: job.sources.clone(),
+            mailbox: job.mailbox.clone(),
+            port,
+            wildcard,
+            fqdn,
+            unknown: false,
+            dga: false,
+            squat: None,
+        }));
+    }
+    if options.no_extract {
+        // converting to unicode lowercases the host as well
+        let idn = options
+            .idn
+            .or((!options.preserve_case).then_some(Idn::Unicode));
+        let host = normalize::clean(host, idn);
+        let host = normalize::strip_prefixes(&host, &options.prefixes, true).to_string();
+        if host.is_empty() {
+            return Err("no host".to_string());
+        }
+        return Ok(Some(JobResult {
+            input: job_host.clone(),
+            subdomain: String::new(),
+            domain: host,
+            suffix: String::new(),
+            sources: job.sources.clone(),
+            mailbox: job.mailbox.clone(),
+            port,
+            wildcard,
+            fqdn,
+            unknown: false,
+            dga: false,
+            squat: None,
+        }));
+    }
+    // the suffixes only match in lowercase
+    let extractor = match options.extractor.extract(&host.to_lowercase()) {
+        Ok(extractor) => extractor,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let (mut subdomain, mut domain, mut suffix, unknown) =
+        match (extractor.subdomain, extractor.domain, extractor.suffix) {
+            (subdomain, Some(domain), Some(suffix)) => {
+                (subdomain.unwrap_or_default(), domain, suffix, false)
+            }
+            // a name such as host.corp is split up as if its last label were listed
+            (_, _, None) if options.unknown != Unknown::Drop => {
+                let host = host.to_lowercase();
+                match host.rsplit_once('.') {
+                    Some((rest, suffix)) => match rest.rsplit_once('.') {
+                        Some((subdomain, domain)) => (
+                            subdomain.to_string(),
+                            domain.to_string(),
+                            suffix.to_string(),
+                            true,
+                        ),
+                        None => (String::new(), rest.to_string(), suffix.to_string(), true),
+                    },
+                    None => (String::new(), host, String::new(), true),
+                }
+            }
+            _ => return Err("no registrable domain".to_string()),
         };
 
-        root_domain.push_str(&domain);
-        root_domain.push_str(".");
-        root_domain.push_str(&suffix);
+    // the suffixes are matched in unicode, so names are only converted after
+    if options.preserve_case {
+        if let Some([s, d, x]) = normalize::recase(host, [&subdomain, &domain, &suffix]) {
+            (subdomain, domain, suffix) = (s, d, x);
+        }
+    }
+    if let Some(idn) = options.idn {
+        subdomain = idn.convert(&subdomain);
+        domain = idn.convert(&domain);
+        suffix = idn.convert(&suffix);
+    }
+
+    subdomain = normalize::strip_prefixes(&subdomain, &options.prefixes, false).to_string();
 
-        println!("{}", root_domain.to_string());
+    // subdomain labels move into the domain until it is deep enough
+    if let Some(level) = options.level {
+        let labels: Vec<&str> = subdomain.split('.').filter(|l| !l.is_empty()).collect();
+        let depth = level - 2;
+        if labels.len() < depth {
+            return Ok(None);
+        }
+        let (rest, kept) = labels.split_at(labels.len() - depth);
+        if !kept.is_empty() {
+            domain = format!("{}.{}", kept.join("."), domain);
+        }
+        subdomain = rest.join(".");
     }
+
+    Ok(Some(JobResult {
+        input: job_host,
+        subdomain,
+        domain,
+        suffix,
+        sources: job.sources.clone(),
+        mailbox: job.mailbox.clone(),
+        port,
+        wildcard,
+        fqdn,
+        unknown,
+        dga: false,
+        squat: None,
+    }))
 }
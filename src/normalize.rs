@@ -0,0 +1,382 @@
+use percent_encoding::percent_decode_str;
+use std::borrow::Cow;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use unicode_normalization::UnicodeNormalization;
+
+/// What to do with the port of a host.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Ports {
+    /// Drop it before the root domain is extracted.
+    #[default]
+    Strip,
+    /// Print it along with the root domain.
+    Keep,
+    /// Only print hosts that have one, along with it.
+    Only,
+}
+
+impl Ports {
+    pub fn new(name: &str) -> Ports {
+        match name {
+            "keep" => Ports::Keep,
+            "only" => Ports::Only,
+            _ => Ports::Strip,
+        }
+    }
+}
+
+/// What to do with hosts that are ip addresses.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Ips {
+    /// Leave them out.
+    #[default]
+    Drop,
+    /// Print them as they are, in place of a root domain.
+    Keep,
+    /// Print them to an output of their own.
+    Separate,
+}
+
+impl Ips {
+    pub fn new(name: &str) -> Ips {
+        match name {
+            "keep" => Ips::Keep,
+            "separate" => Ips::Separate,
+            _ => Ips::Drop,
+        }
+    }
+}
+
+/// What to do with hosts whose suffix is not on the public suffix list.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Unknown {
+    /// Leave them out.
+    #[default]
+    Drop,
+    /// Print them with their last label taken as the suffix.
+    Keep,
+    /// Print them to an output of their own.
+    Separate,
+}
+
+impl Unknown {
+    pub fn new(name: &str) -> Unknown {
+        match name {
+            "keep" => Unknown::Keep,
+            "separate-file" => Unknown::Separate,
+            _ => Unknown::Drop,
+        }
+    }
+}
+
+/// Returns the address a host is an ip literal of, ip v6 addresses either
+/// bare or in brackets.
+pub fn ip(host: &str) -> Option<IpAddr> {
+    let host = match host.strip_prefix('[') {
+        Some(host) => host.strip_suffix(']')?,
+        None => host,
+    };
+    host.parse().ok()
+}
+
+/// How internationalized domain names are printed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Idn {
+    /// In unicode, decoding any `xn--` labels.
+    Unicode,
+    /// In punycode.
+    Ascii,
+}
+
+impl Idn {
+    pub fn new(name: &str) -> Idn {
+        match name {
+            "ascii" => Idn::Ascii,
+            _ => Idn::Unicode,
+        }
+    }
+
+    /// Converts a part of a host, leaving it as it is when it is not a valid
+    /// domain name.
+    pub fn convert(&self, name: &str) -> String {
+        if name.is_empty() {
+            return String::new();
+        }
+        match self {
+            Idn::Unicode => match idna::domain_to_unicode(name) {
+                (name, Ok(())) => name,
+                (_, Err(_)) => name.to_string(),
+            },
+            Idn::Ascii => idna::domain_to_ascii(name).unwrap_or_else(|_| name.to_string()),
+        }
+    }
+}
+
+/// Returns the addresses of a network in CIDR notation such as
+/// `10.0.0.0/28`, at most `limit` of them, and whether there were more.
+pub fn expand(cidr: &str, limit: usize) -> Option<(Vec<String>, bool)> {
+    let (address, prefix) = cidr.trim().split_once('/')?;
+    let address: IpAddr = address.parse().ok()?;
+    let prefix: u32 = prefix.parse().ok()?;
+    let bits = if address.is_ipv4() { 32 } else { 128 };
+    if prefix > bits {
+        return None;
+    }
+
+    let size = 1u128.checked_shl(bits - prefix).unwrap_or(u128::MAX);
+    // a shift by the full 128 bits of a /0 leaves no network bits
+    let mask = u128::MAX.checked_shl(bits - prefix).unwrap_or(0);
+    let start = match address {
+        IpAddr::V4(address) => u32::from(address) as u128,
+        IpAddr::V6(address) => u128::from(address),
+    } & mask;
+    let count = size.min(limit as u128) as usize;
+    let addresses = (0..count as u128)
+        .map(|i| match address {
+            IpAddr::V4(_) => Ipv4Addr::from((start + i) as u32).to_string(),
+            IpAddr::V6(_) => Ipv6Addr::from(start + i).to_string(),
+        })
+        .collect();
+    Some((addresses, size > limit as u128))
+}
+
+/// Returns the host of a url, or of a bare `host:port`, and its port,
+/// dropping the scheme, credentials, path, query and fragment around it.
+/// Scheme relative urls such as `//cdn.example.com/app.js` are urls too.
+/// Anything that is not a url is returned as it is.
+pub fn host(input: &str) -> (&str, Option<&str>) {
+    let mut host = input.trim();
+
+    if let Some(rest) = host.strip_prefix("//") {
+        host = rest;
+    } else if let Some((scheme, rest)) = host.split_once("://") {
+        if is_scheme(scheme) {
+            host = rest;
+        }
+    }
+    if let Some(end) = host.find(['/', '?', '#']) {
+        host = &host[..end];
+    }
+    // the password may itself contain an @, the host never does
+    if let Some((_, rest)) = host.rsplit_once('@') {
+        host = rest;
+    }
+
+    if host.starts_with('[') {
+        // an ip v6 address keeps its colons
+        if let Some(end) = host.find(']') {
+            let port = host[end + 1..]
+                .strip_prefix(':')
+                .filter(|port| is_port(port));
+            return (&host[..=end], port);
+        }
+    } else if host.matches(':').count() > 1 {
+        // a bare ip v6 address can not have a port
+        return (host, None);
+    } else if let Some((name, port)) = host.rsplit_once(':') {
+        if port.is_empty() || is_port(port) {
+            return (name, Some(port).filter(|port| !port.is_empty()));
+        }
+    }
+    (host, None)
+}
+
+/// The Unicode normalization form hosts are brought into, so names that
+/// only differ in how their characters are composed come out the same.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Form {
+    /// Canonical composition, `e` and a combining accent become `é`.
+    Nfc,
+    /// Compatibility composition, which also folds look-alike forms such as
+    /// fullwidth letters and ligatures.
+    Nfkc,
+}
+
+impl Form {
+    pub fn new(name: &str) -> Form {
+        match name {
+            "nfkc" => Form::Nfkc,
+            _ => Form::Nfc,
+        }
+    }
+
+    /// Normalizes a host, leaving ascii ones as they are.
+    pub fn apply<'a>(&self, host: &'a str) -> Cow<'a, str> {
+        if host.is_ascii() {
+            return Cow::Borrowed(host);
+        }
+        match self {
+            Form::Nfc => Cow::Owned(host.nfc().collect()),
+            Form::Nfkc => Cow::Owned(host.nfkc().collect()),
+        }
+    }
+}
+
+/// Decodes the percent-encoding of a host taken from a url, such as `%2e`
+/// for a dot, leaving it as it is when it does not decode to utf-8.
+pub fn decode(host: &str) -> Cow<'_, str> {
+    if !host.contains('%') {
+        return Cow::Borrowed(host);
+    }
+    percent_decode_str(host)
+        .decode_utf8()
+        .unwrap_or(Cow::Borrowed(host))
+}
+
+/// Returns the urls in the query string of a url, percent-decoded, and in
+/// turn those in theirs, such as `https://b.example.com` out of
+/// `https://a.example.com/redirect?url=https%3A%2F%2Fb.example.com`.
+pub fn nested(url: &str) -> Vec<String> {
+    let mut urls = vec![];
+    let mut pending = vec![url.to_string()];
+    // a redirect to a redirect to a redirect is as deep as it gets
+    for _ in 0..4 {
+        let mut found = vec![];
+        for url in &pending {
+            let query = match url.split_once('?') {
+                Some((_, query)) => query.split('#').next().unwrap_or_default(),
+                None => continue,
+            };
+            for pair in query.split('&') {
+                let value = pair.split_once('=').map_or(pair, |(_, value)| value);
+                let value = decode(&value.replace('+', " ")).trim().to_string();
+                if value.contains("://") || value.starts_with("//") {
+                    found.push(value);
+                }
+            }
+        }
+        if found.is_empty() {
+            break;
+        }
+        urls.extend(found.iter().cloned());
+        pending = found;
+    }
+    urls
+}
+
+/// Tidies up a host from dirty tool output: without surrounding
+/// whitespace, quotes or separators, and lowercased when `lowercase`.
+pub fn tidy(input: &str, lowercase: bool) -> String {
+    let host = input
+        .trim_matches(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '`' | ',' | ';'));
+    if lowercase {
+        host.to_lowercase()
+    } else {
+        host.to_string()
+    }
+}
+
+/// Puts the case of `host` back onto the subdomain, domain and suffix that
+/// were extracted from its lowercase form, when they still spell it out.
+pub fn recase(host: &str, parts: [&str; 3]) -> Option<[String; 3]> {
+    let [subdomain, domain, suffix] = parts;
+    let joined = match subdomain {
+        "" => format!("{}.{}", domain, suffix),
+        subdomain => format!("{}.{}.{}", subdomain, domain, suffix),
+    };
+    if !host.is_ascii() || !joined.eq_ignore_ascii_case(host) {
+        return None;
+    }
+    let suffix_start = host.len() - suffix.len();
+    let domain_start = suffix_start - 1 - domain.len();
+    Some([
+        host[..subdomain.len()].to_string(),
+        host[domain_start..suffix_start - 1].to_string(),
+        host[suffix_start..].to_string(),
+    ])
+}
+
+/// Cleans a host up to print it whole: without leading or trailing dots,
+/// and with its labels in the `idn` form when one is given.
+pub fn clean(host: &str, idn: Option<Idn>) -> String {
+    let host = host.trim_matches('.');
+    match idn {
+        Some(idn) => idn.convert(host),
+        None => host.to_string(),
+    }
+}
+
+/// Strips the leading labels of a name that are among `prefixes`, so
+/// `www.example.com` becomes `example.com`. With `keep_dot` at least two
+/// labels are left, for whole hosts that still need their domain.
+pub fn strip_prefixes<'a>(name: &'a str, prefixes: &[String], keep_dot: bool) -> &'a str {
+    let mut name = name;
+    while let Some((label, rest)) = name.split_once('.') {
+        if (keep_dot && !rest.contains('.'))
+            || !prefixes.iter().any(|p| label.eq_ignore_ascii_case(p))
+        {
+            return name;
+        }
+        name = rest;
+    }
+    if !keep_dot && prefixes.iter().any(|p| name.eq_ignore_ascii_case(p)) {
+        return "";
+    }
+    name
+}
+
+/// Strips the `*.` labels off a wildcard host such as `*.example.com`,
+/// returning whether there were any.
+pub fn wildcard(host: &str) -> (&str, bool) {
+    let mut name = host;
+    while let Some(rest) = name.strip_prefix("*.") {
+        name = rest;
+    }
+    (name, name.len() != host.len())
+}
+
+/// Strips the trailing dot off a fully qualified name such as
+/// `example.com.`, returning whether there was one.
+pub fn trailing_dot(host: &str) -> (&str, bool) {
+    match host.strip_suffix('.') {
+        Some(name) => (name, true),
+        None => (host, false),
+    }
+}
+
+/// Checks a host against the RFC 1123 rules for hostnames: at most 253
+/// characters, in labels of 1 to 63 letters, digits and inner hyphens.
+/// Internationalized names are checked in their punycode form.
+pub fn validate(host: &str) -> Result<(), String> {
+    let ascii = match host.is_ascii() {
+        true => host.to_string(),
+        false => match idna::domain_to_ascii(host) {
+            Ok(ascii) => ascii,
+            Err(_) => return Err("invalid internationalized name".to_string()),
+        },
+    };
+    if ascii.len() > 253 {
+        return Err("longer than 253 characters".to_string());
+    }
+    for label in ascii.split('.') {
+        if label.is_empty() {
+            return Err("empty label".to_string());
+        }
+        if label.len() > 63 {
+            return Err(format!("label {} is longer than 63 characters", label));
+        }
+        if let Some(c) = label
+            .chars()
+            .find(|c| !c.is_ascii_alphanumeric() && *c != '-')
+        {
+            return Err(format!("label {} has the character {:?}", label, c));
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err(format!("label {} starts or ends with a hyphen", label));
+        }
+    }
+    Ok(())
+}
+
+fn is_port(port: &str) -> bool {
+    !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Whether `scheme` can start a url, a letter followed by letters, digits,
+/// `+`, `-` or `.`.
+fn is_scheme(scheme: &str) -> bool {
+    scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
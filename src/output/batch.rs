@@ -0,0 +1,88 @@
+use reqwest::blocking::Client;
+use reqwest::header::CONTENT_TYPE;
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How long an item may wait for its batch to fill up.
+const INTERVAL: Duration = Duration::from_secs(5);
+
+/// How many times a request is retried before its batch is dropped.
+const MAX_RETRIES: u32 = 5;
+
+/// Collects the items sent to it into batches of up to `size` on a thread of
+/// its own, handing each batch to `flush` once it is full or has waited for
+/// long enough. At most `bound` items are queued, so a slow consumer holds
+/// up the senders instead of filling up memory.
+pub fn spawn<T, F>(size: usize, bound: usize, mut flush: F) -> (SyncSender<T>, JoinHandle<()>)
+where
+    T: Send + 'static,
+    F: FnMut(Vec<T>) + Send + 'static,
+{
+    let (tx, rx) = mpsc::sync_channel::<T>(bound);
+    let thread = std::thread::spawn(move || {
+        let mut batch = Vec::new();
+        let mut deadline = Instant::now() + INTERVAL;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let closed = match rx.recv_timeout(timeout) {
+                Ok(item) => {
+                    batch.push(item);
+                    false
+                }
+                Err(RecvTimeoutError::Timeout) => false,
+                Err(RecvTimeoutError::Disconnected) => true,
+            };
+            if batch.len() >= size || Instant::now() >= deadline || closed {
+                if !batch.is_empty() {
+                    flush(std::mem::take(&mut batch));
+                }
+                deadline = Instant::now() + INTERVAL;
+            }
+            if closed {
+                return;
+            }
+        }
+    });
+    (tx, thread)
+}
+
+/// Posts a body, backing off and retrying when the endpoint fails or asks to
+/// slow down. Returns the response body, or `None` once the request has
+/// been given up on.
+pub fn post(client: &Client, url: &str, content_type: &str, body: String) -> Option<String> {
+    let mut attempts = 0;
+    loop {
+        let error = match client
+            .post(url)
+            .header(CONTENT_TYPE, content_type)
+            .body(body.clone())
+            .send()
+        {
+            Ok(response) if response.status().is_success() => {
+                return Some(response.text().unwrap_or_default())
+            }
+            Ok(response)
+                if response.status().is_client_error() && response.status().as_u16() != 429 =>
+            {
+                warn!("{} rejected the request: {}", url, response.status());
+                return None;
+            }
+            Ok(response) => response.status().to_string(),
+            Err(e) => e.to_string(),
+        };
+        attempts += 1;
+        if attempts > MAX_RETRIES {
+            warn!(
+                "error posting to {}: {}, giving up after {} retries",
+                url, error, MAX_RETRIES
+            );
+            return None;
+        }
+        warn!(
+            "error posting to {}: {}, retrying ({}/{})",
+            url, error, attempts, MAX_RETRIES
+        );
+        std::thread::sleep(Duration::from_secs(1 << (attempts - 1)));
+    }
+}
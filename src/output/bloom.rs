@@ -0,0 +1,51 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// A bloom filter sized for a number of items and a false positive rate, to
+/// tell apart results that have been seen before in bounded memory.
+pub struct Bloom {
+    bits: Vec<u64>,
+    /// The number of bits.
+    size: u64,
+    /// The number of hashes per item.
+    hashes: u32,
+}
+
+impl Bloom {
+    pub fn new(items: u64, rate: f64) -> Bloom {
+        let items = items.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let size = ((-items * rate.ln() / (ln2 * ln2)).ceil() as u64).max(64);
+        let hashes = ((size as f64 / items * ln2).round() as u32).clamp(1, 32);
+        Bloom {
+            bits: vec![0; size.div_ceil(64) as usize],
+            size,
+            hashes,
+        }
+    }
+
+    /// Adds an item, returning whether it was not in the filter yet.
+    pub fn insert(&mut self, item: &str) -> bool {
+        let (a, b) = hash(item);
+        let mut new = false;
+        for i in 0..self.hashes as u64 {
+            // double hashing gives as many independent positions as needed
+            let bit = a.wrapping_add(i.wrapping_mul(b)) % self.size;
+            let (word, mask) = ((bit / 64) as usize, 1 << (bit % 64));
+            if self.bits[word] & mask == 0 {
+                self.bits[word] |= mask;
+                new = true;
+            }
+        }
+        new
+    }
+}
+
+fn hash(item: &str) -> (u64, u64) {
+    let mut hasher = DefaultHasher::new();
+    item.hash(&mut hasher);
+    let a = hasher.finish();
+    // hashing on from the first value gives an unrelated second one
+    a.hash(&mut hasher);
+    (a, hasher.finish() | 1)
+}
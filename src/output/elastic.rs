@@ -0,0 +1,88 @@
+use reqwest::blocking::Client;
+use serde_json::{json, Value};
+use std::sync::mpsc::SyncSender;
+use std::thread::JoinHandle;
+
+use super::{batch, format};
+use crate::JobResult;
+
+/// How many documents go into one bulk request at most.
+const BATCH_SIZE: usize = 500;
+
+/// Indexes every result as a document through the Elasticsearch bulk api.
+/// Requests are made on a thread of their own, and once a few batches are
+/// waiting on a slow cluster the output waits for it to catch up.
+pub struct Elastic {
+    tx: Option<SyncSender<Value>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Elastic {
+    pub fn new(url: &str, index: &str) -> Elastic {
+        let url = format!("{}/_bulk", url.trim_end_matches('/'));
+        let action = json!({ "index": { "_index": index } }).to_string();
+        // the blocking client has to be made off the async runtime
+        let mut client = None;
+        let (tx, thread) = batch::spawn(BATCH_SIZE, 4 * BATCH_SIZE, move |docs: Vec<Value>| {
+            let client = client.get_or_insert_with(Client::new);
+            let mut body = String::new();
+            for doc in &docs {
+                body.push_str(&action);
+                body.push('\n');
+                body.push_str(&doc.to_string());
+                body.push('\n');
+            }
+            if let Some(response) = batch::post(client, &url, "application/x-ndjson", body) {
+                report(&url, &response);
+            }
+        });
+        Elastic {
+            tx: Some(tx),
+            thread: Some(thread),
+        }
+    }
+
+    pub fn send(&mut self, result: &JobResult) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(format::object(result));
+        }
+    }
+
+    /// Indexes what is still queued and waits for it to be done.
+    pub fn close(&mut self) {
+        self.tx = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Warns about the documents of a bulk request that failed to index.
+fn report(url: &str, response: &str) {
+    let response: Value = match serde_json::from_str(response) {
+        Ok(response) => response,
+        Err(_) => return,
+    };
+    if response["errors"].as_bool() != Some(true) {
+        return;
+    }
+    let failed: Vec<&Value> = response["items"]
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item["index"]["error"].as_object().map(|_| item))
+                .collect()
+        })
+        .unwrap_or_default();
+    if let Some(first) = failed.first() {
+        warn!(
+            "{} could not index {} documents: {}",
+            url,
+            failed.len(),
+            first["index"]["error"]["reason"]
+                .as_str()
+                .unwrap_or("unknown error")
+        );
+    }
+}
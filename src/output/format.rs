@@ -0,0 +1,223 @@
+use quick_xml::escape::escape;
+use serde_json::{json, Value};
+
+use super::template::{Field, Template};
+use crate::JobResult;
+
+/// How each result is written out.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum OutputFormat {
+    /// One root domain per line.
+    #[default]
+    Text,
+    /// A json array with an object per result.
+    Json,
+    /// One json object per line.
+    Jsonl,
+    /// Delimiter separated values with a header row, csv or tsv.
+    Csv { delimiter: u8 },
+    /// An xml document with a `host` element per result.
+    Xml,
+    /// A graphviz graph with edges from each suffix to its root domains and
+    /// on down their subdomains.
+    Dot,
+    /// A line per result filled in from a `--format` template.
+    Template(Template),
+}
+
+/// The columns of csv output.
+const CSV_HEADER: [&str; 5] = ["input", "root", "subdomain", "domain", "suffix"];
+
+impl OutputFormat {
+    /// Builds the output format from the `--output-format` and `--delimiter`
+    /// arguments.
+    pub fn new(name: &str, delimiter: Option<u8>) -> OutputFormat {
+        match name {
+            "json" => OutputFormat::Json,
+            "jsonl" => OutputFormat::Jsonl,
+            "xml" => OutputFormat::Xml,
+            "dot" => OutputFormat::Dot,
+            "csv" => OutputFormat::Csv {
+                delimiter: delimiter.unwrap_or(b','),
+            },
+            "tsv" => OutputFormat::Csv {
+                delimiter: delimiter.unwrap_or(b'\t'),
+            },
+            _ => OutputFormat::Text,
+        }
+    }
+
+    /// Text written before the first result.
+    pub fn header(&self) -> String {
+        match self {
+            OutputFormat::Json => "[\n".to_string(),
+            OutputFormat::Xml => {
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<hosts>\n".to_string()
+            }
+            OutputFormat::Csv { delimiter } => csv_row(&CSV_HEADER, *delimiter),
+            // a strict graph draws the edges shared by many hosts only once
+            OutputFormat::Dot => "strict digraph hosts {\n".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// Formats a single result, `first` telling whether it is the first one.
+    /// Text is colored when `color` is set.
+    pub fn record(&self, result: &JobResult, first: bool, color: bool) -> String {
+        match self {
+            OutputFormat::Text => format!("{}\n", line(result, color)),
+            OutputFormat::Template(template) => format!("{}\n", template.render(result, color)),
+            OutputFormat::Json if first => object(result).to_string(),
+            OutputFormat::Json => format!(",\n{}", object(result)),
+            OutputFormat::Jsonl => format!("{}\n", object(result)),
+            OutputFormat::Xml => format!("{}\n", element(result)),
+            OutputFormat::Dot => edges(result),
+            OutputFormat::Csv { delimiter } => {
+                let root = result.root();
+                let row = [
+                    result.input.as_str(),
+                    root.as_str(),
+                    result.subdomain.as_str(),
+                    result.domain.as_str(),
+                    result.suffix.as_str(),
+                ];
+                csv_row(&row, *delimiter)
+            }
+        }
+    }
+
+    /// Whether every result is a line of its own, without a header.
+    pub fn is_line(&self) -> bool {
+        matches!(self, OutputFormat::Text | OutputFormat::Template(_))
+    }
+
+    /// Text written after the last result.
+    pub fn footer(&self, empty: bool) -> String {
+        match self {
+            OutputFormat::Json if empty => "]\n".to_string(),
+            OutputFormat::Json => "\n]\n".to_string(),
+            OutputFormat::Xml => "</hosts>\n".to_string(),
+            OutputFormat::Dot => "}\n".to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// Formats a result the way hostparser always has: the root domain, with the
+/// mailbox in front and the data sources after it when the input has them.
+pub fn text(result: &JobResult) -> String {
+    line(result, false)
+}
+
+fn line(result: &JobResult, color: bool) -> String {
+    let mut line = if color {
+        Field::Root.paint(result)
+    } else {
+        result.root()
+    };
+    if let Some(mailbox) = &result.mailbox {
+        line = format!("{}@{}", mailbox, line);
+    }
+    match &result.port {
+        // the colons of an ip v6 address need brackets to tell the port apart
+        Some(port) if result.is_ip() && result.domain.contains(':') => {
+            line = format!("[{}]:{}", line, port);
+        }
+        Some(port) => line = format!("{}:{}", line, port),
+        None => {}
+    }
+    if !result.sources.is_empty() {
+        line = format!("{}\t{}", line, result.sources.join(","));
+    }
+    if result.wildcard {
+        line += "\twildcard";
+    }
+    line
+}
+
+/// The xml element of a result, with the optional attributes only when
+/// present.
+fn element(result: &JobResult) -> String {
+    let mut element = format!(
+        "  <host input=\"{}\" root=\"{}\" subdomain=\"{}\" domain=\"{}\" suffix=\"{}\"",
+        escape(&result.input),
+        escape(&result.root()),
+        escape(&result.subdomain),
+        escape(&result.domain),
+        escape(&result.suffix),
+    );
+    if !result.sources.is_empty() {
+        element += &format!(" sources=\"{}\"", escape(&result.sources.join(",")));
+    }
+    if let Some(mailbox) = &result.mailbox {
+        element += &format!(" mailbox=\"{}\"", escape(mailbox));
+    }
+    if let Some(port) = &result.port {
+        element += &format!(" port=\"{}\"", escape(port));
+    }
+    if result.wildcard {
+        element += " wildcard=\"true\"";
+    }
+    element + "/>"
+}
+
+/// The dot edges of a result, from the suffix to the root domain and then
+/// one label at a time down to the full host.
+fn edges(result: &JobResult) -> String {
+    let mut parent = result.suffix.clone();
+    let mut node = result.root();
+    // a host kept whole stands on its own
+    if result.is_whole() {
+        return format!("  {};\n", quote(&node));
+    }
+    let mut edges = format!("  {} -> {};\n", quote(&parent), quote(&node));
+    if !result.subdomain.is_empty() {
+        for label in result.subdomain.rsplit('.') {
+            parent = node;
+            node = format!("{}.{}", label, parent);
+            edges += &format!("  {} -> {};\n", quote(&parent), quote(&node));
+        }
+    }
+    edges
+}
+
+/// A dot node id.
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The json object of a result, with the optional fields only when present.
+pub fn object(result: &JobResult) -> Value {
+    let mut object = json!({
+        "input": result.input,
+        "root": result.root(),
+        "subdomain": result.subdomain,
+        "domain": result.domain,
+        "suffix": result.suffix,
+    });
+    if !result.sources.is_empty() {
+        object["sources"] = json!(result.sources);
+    }
+    if let Some(mailbox) = &result.mailbox {
+        object["mailbox"] = json!(mailbox);
+    }
+    if let Some(port) = &result.port {
+        object["port"] = json!(port);
+    }
+    if result.wildcard {
+        object["wildcard"] = json!(true);
+    }
+    object
+}
+
+/// Formats a single csv row, quoting the fields that need it.
+fn csv_row(fields: &[&str], delimiter: u8) -> String {
+    let mut writer = ::csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(vec![]);
+    let _ = writer.write_record(fields);
+    match writer.into_inner() {
+        Ok(row) => String::from_utf8_lossy(&row).into_owned(),
+        Err(_) => String::new(),
+    }
+}
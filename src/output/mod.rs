@@ -0,0 +1,550 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::io;
+use std::sync::mpsc::{self, SyncSender, TryRecvError};
+use std::sync::{Mutex, RwLock};
+use std::thread::JoinHandle;
+
+use crate::JobResult;
+
+mod batch;
+mod bloom;
+mod elastic;
+mod format;
+mod report;
+mod sink;
+mod sqlite;
+mod syslog;
+mod template;
+mod tree;
+mod webhook;
+
+use self::bloom::Bloom;
+use self::elastic::Elastic;
+pub use self::format::{text, OutputFormat};
+use self::report::Report;
+use self::sink::Sink;
+use self::syslog::Syslog;
+pub use self::template::{Field, Template};
+use self::tree::Tree;
+use self::webhook::Webhook;
+
+/// Options for how and where the results are written.
+#[derive(Clone, Debug, Default)]
+pub struct OutputOptions {
+    /// The files to write to, `-` for stdout or a `sqlite://` database.
+    /// Without any the results go to stdout.
+    pub paths: Vec<String>,
+    /// Writes each result to a file in a directory named after this part of
+    /// it, the suffix or the root domain.
+    pub split: Option<(Field, String)>,
+    /// Urls every new root domain is posted to.
+    pub webhooks: Vec<String>,
+    /// Syslog servers every root domain is sent to.
+    pub syslogs: Vec<String>,
+    /// An Elasticsearch cluster and the index every result is added to.
+    pub elastic: Option<(String, String)>,
+    /// Adds to the end of the files instead of replacing them.
+    pub append: bool,
+    /// Writes the files in place rather than next to them, for runs that
+    /// are checkpointed and may be resumed.
+    pub in_place: bool,
+    /// Compresses every output, otherwise files are compressed by their
+    /// extension.
+    pub compression: Option<Compression>,
+    pub format: OutputFormat,
+    /// The part of a result that unique results, counts and sorting by root
+    /// go by, the root domain unless only another part is printed.
+    pub key: Field,
+    /// Colors the parts of each host when writing text to a terminal.
+    pub color: bool,
+    /// Separates the results with NUL bytes instead of newlines.
+    pub print0: bool,
+    /// Splits the subdomain into its labels and writes each on its own.
+    pub labels: bool,
+    /// Whether each root domain is only written the first time it is seen.
+    pub unique: bool,
+    /// The path ip addresses are written to instead of with the other
+    /// results.
+    pub ips: Option<String>,
+    /// The path hosts with a suffix that is not on the public suffix list
+    /// are written to instead of with the other results.
+    pub unknown: Option<String>,
+    /// Whether results are written in the order of their hosts in the
+    /// input, with an empty line for each host that had none.
+    pub ordered: bool,
+    /// Tells seen root domains apart with a bloom filter sized for this many
+    /// of them at this false positive rate, instead of keeping every one.
+    pub unique_approx: Option<(u64, f64)>,
+    /// Holds the results back until the end and writes them in this order.
+    pub sort: Option<SortOrder>,
+    /// Counts the results per root domain and writes the counts at the end.
+    pub count: bool,
+    /// Writes only this many of the biggest counts.
+    pub top: Option<usize>,
+    /// Groups the subdomains under their root domain and writes the
+    /// hierarchy at the end.
+    pub tree: bool,
+    /// Writes a markdown report of the root domains and suffixes at the end.
+    pub report: bool,
+}
+
+/// How the output is compressed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub fn new(name: &str) -> Option<Compression> {
+        match name {
+            "gzip" | "gz" => Some(Compression::Gzip),
+            "zstd" | "zst" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Picks the compression from a `.gz` or `.zst` extension.
+    pub fn for_path(path: &str) -> Option<Compression> {
+        Compression::new(path.rsplit_once('.')?.1)
+    }
+}
+
+/// How sorted results are ordered.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortOrder {
+    /// Lexicographically by root domain.
+    Root,
+    /// By suffix, then domain, then subdomain.
+    Suffix,
+}
+
+/// How many results may wait for the writer before the workers block.
+const QUEUE_SIZE: usize = 4096;
+
+/// Where the results are written, shared by every worker. Workers only
+/// drop duplicates and queue the results; a writer thread formats them and
+/// owns every sink, so the workers never wait on each other's writes.
+pub struct Output {
+    key: Field,
+    labels: bool,
+    seen: Option<Mutex<Seen>>,
+    ordered: bool,
+    /// Where ip addresses go when they are written on their own.
+    ips: Option<Box<Output>>,
+    unknown: Option<Box<Output>>,
+    tx: RwLock<Option<SyncSender<Entry>>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// What the writer thread is sent.
+enum Entry {
+    /// What a host came to, along with its position in the input.
+    Result(u64, Option<JobResult>),
+    /// Asks for everything sent before to be synced to disk, answering once
+    /// it is.
+    Sync(SyncSender<()>),
+}
+
+/// The results written so far, when only unique ones are wanted.
+enum Seen {
+    /// Every result written, and with a checkpoint those written since it
+    /// was last saved.
+    Exact(HashSet<String>, Option<Vec<String>>),
+    Approx(Bloom),
+}
+
+impl Seen {
+    fn insert(&mut self, key: String) -> bool {
+        match self {
+            Seen::Exact(seen, Some(fresh)) if !seen.contains(&key) => {
+                fresh.push(key.clone());
+                seen.insert(key)
+            }
+            Seen::Exact(seen, _) => seen.insert(key),
+            Seen::Approx(bloom) => bloom.insert(&key),
+        }
+    }
+}
+
+/// The writer thread's side of the output.
+struct Writer {
+    format: OutputFormat,
+    key: Field,
+    sort: Option<SortOrder>,
+    count: bool,
+    top: Option<usize>,
+    tree: bool,
+    report: bool,
+    sinks: Vec<Sink>,
+    written: u64,
+    buffer: Vec<JobResult>,
+    counts: HashMap<String, u64>,
+    roots: BTreeMap<String, Tree>,
+    summary: Report,
+    /// The next host to write and the results that came in ahead of it,
+    /// when writing in input order.
+    order: Option<(u64, BTreeMap<u64, Option<JobResult>>)>,
+}
+
+impl Writer {
+    /// Takes in what the `seq`th host came to, holding it back until every
+    /// host before it is in when writing in input order.
+    fn take(&mut self, seq: u64, result: Option<JobResult>) {
+        let (next, pending) = match &mut self.order {
+            Some(order) => order,
+            None => {
+                if let Some(result) = result {
+                    self.add(result);
+                }
+                return;
+            }
+        };
+        pending.insert(seq, result);
+        let mut ready = vec![];
+        while let Some(result) = pending.remove(next) {
+            ready.push(result);
+            *next += 1;
+        }
+        for result in ready {
+            match result {
+                Some(result) => self.add(result),
+                // keep the lines of the output in step with the hosts
+                None if self.format.is_line() => self.write_all(b"\n"),
+                None => {}
+            }
+        }
+    }
+
+    /// Takes in a result, either writing it right away or keeping it for
+    /// the end of the run.
+    fn add(&mut self, result: JobResult) {
+        if self.count {
+            *self.counts.entry(key(self.key, &result)).or_insert(0) += 1;
+        } else if self.tree {
+            self.roots
+                .entry(result.root())
+                .or_default()
+                .insert(&result.subdomain);
+        } else if self.report {
+            self.summary.add(&result);
+        } else if self.sort.is_some() {
+            self.buffer.push(result);
+        } else {
+            self.emit(&result);
+        }
+    }
+
+    fn emit(&mut self, result: &JobResult) {
+        let first = self.written == 0;
+        for sink in &mut self.sinks {
+            sink.record(&self.format, result, first);
+        }
+        self.written += 1;
+    }
+
+    fn write_all(&mut self, data: &[u8]) {
+        for sink in &mut self.sinks {
+            sink.write_all(data);
+        }
+    }
+
+    fn flush(&mut self) {
+        for sink in &mut self.sinks {
+            sink.flush();
+        }
+    }
+
+    fn sync(&mut self) {
+        for sink in &mut self.sinks {
+            sink.sync();
+        }
+    }
+
+    /// Closes off the output and flushes whatever is still buffered once
+    /// every result has been written.
+    fn finish(&mut self) {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        match self.sort {
+            Some(SortOrder::Root) => buffer.sort_by(|a, b| {
+                key(self.key, a)
+                    .cmp(&key(self.key, b))
+                    .then_with(|| a.input.cmp(&b.input))
+            }),
+            Some(SortOrder::Suffix) => buffer.sort_by(|a, b| {
+                (&a.suffix, &a.domain, &a.subdomain).cmp(&(&b.suffix, &b.domain, &b.subdomain))
+            }),
+            None => {}
+        }
+        for result in &buffer {
+            self.emit(result);
+        }
+
+        // the biggest footprint first
+        let mut counts: Vec<_> = self.counts.drain().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        if let Some(top) = self.top {
+            counts.truncate(top);
+        }
+        for (root, count) in counts {
+            self.write_all(format!("{}\t{}\n", count, root).as_bytes());
+        }
+
+        let roots = std::mem::take(&mut self.roots);
+        for (root, tree) in &roots {
+            let mut text = format!("{}\n", root).into_bytes();
+            if let Err(e) = tree.write(&mut text, 1) {
+                fail(e);
+            }
+            self.write_all(&text);
+        }
+
+        if self.report {
+            let report = self.summary.markdown();
+            self.write_all(report.as_bytes());
+        }
+
+        let footer = self.format.footer(self.written == 0);
+        for sink in &mut self.sinks {
+            sink.close(&footer);
+        }
+    }
+}
+
+impl Output {
+    /// Writes to every one of the paths at once, or to stdout without any.
+    pub fn new(options: &OutputOptions) -> Result<Output, Box<dyn Error + Send + Sync + 'static>> {
+        let mut sinks = Vec::new();
+        if options.paths.is_empty()
+            && options.split.is_none()
+            && options.webhooks.is_empty()
+            && options.syslogs.is_empty()
+            && options.elastic.is_none()
+        {
+            sinks.push(Sink::open("-", options)?);
+        }
+        if let Some((field, dir)) = &options.split {
+            sinks.push(Sink::split(*field, dir, options)?);
+        }
+        for url in &options.webhooks {
+            sinks.push(Sink::Webhook(Webhook::new(url)));
+        }
+        if let Some((url, index)) = &options.elastic {
+            sinks.push(Sink::Elastic(Elastic::new(url, index)));
+        }
+        for url in &options.syslogs {
+            sinks.push(Sink::Syslog(Syslog::connect(url)?));
+        }
+        for path in &options.paths {
+            sinks.push(Sink::open(path, options)?);
+        }
+
+        let mut writer = Writer {
+            format: options.format.clone(),
+            key: options.key,
+            sort: options.sort,
+            count: options.count,
+            top: options.top,
+            tree: options.tree,
+            report: options.report,
+            sinks,
+            written: 0,
+            buffer: Vec::new(),
+            counts: HashMap::new(),
+            roots: BTreeMap::new(),
+            summary: Report::default(),
+            order: options.ordered.then(|| (0, BTreeMap::new())),
+        };
+        let (tx, rx) = mpsc::sync_channel(QUEUE_SIZE);
+        let thread = std::thread::spawn(move || {
+            loop {
+                let entry = match rx.try_recv() {
+                    Ok(entry) => entry,
+                    // a slow input still shows its results as they come
+                    Err(TryRecvError::Empty) => {
+                        writer.flush();
+                        match rx.recv() {
+                            Ok(entry) => entry,
+                            Err(_) => break,
+                        }
+                    }
+                    Err(TryRecvError::Disconnected) => break,
+                };
+                match entry {
+                    Entry::Result(seq, result) => writer.take(seq, result),
+                    Entry::Sync(synced) => {
+                        writer.sync();
+                        let _ = synced.send(());
+                    }
+                }
+            }
+            writer.finish();
+        });
+
+        Ok(Output {
+            key: options.key,
+            labels: options.labels,
+            ordered: options.ordered,
+            ips: match &options.ips {
+                Some(path) => Some(Box::new(Output::side(path, options)?)),
+                None => None,
+            },
+            unknown: match &options.unknown {
+                Some(path) => Some(Box::new(Output::side(path, options)?)),
+                None => None,
+            },
+            seen: match options.unique_approx {
+                Some((items, rate)) => Some(Mutex::new(Seen::Approx(Bloom::new(items, rate)))),
+                // files are only written in place for a checkpoint
+                None if options.unique => Some(Mutex::new(Seen::Exact(
+                    HashSet::new(),
+                    Some(vec![]).filter(|_| options.in_place),
+                ))),
+                None => None,
+            },
+            tx: RwLock::new(Some(tx)),
+            thread: Mutex::new(Some(thread)),
+        })
+    }
+
+    /// An output of its own for the results set apart from the others, in
+    /// the same format.
+    fn side(
+        path: &str,
+        options: &OutputOptions,
+    ) -> Result<Output, Box<dyn Error + Send + Sync + 'static>> {
+        Output::new(&OutputOptions {
+            paths: vec![path.to_string()],
+            format: options.format.clone(),
+            print0: options.print0,
+            append: options.append,
+            in_place: options.in_place,
+            ..OutputOptions::default()
+        })
+    }
+
+    /// Writes the result of the `seq`th host, unless it is a duplicate and
+    /// only unique results are wanted. Hosts without a result only matter
+    /// to ordered output.
+    pub fn write(&self, seq: u64, result: Option<&JobResult>) {
+        let result = match result {
+            Some(result) if self.labels && result.subdomain.contains('.') => {
+                for label in result.subdomain.split('.') {
+                    let result = JobResult {
+                        subdomain: label.to_string(),
+                        ..result.clone()
+                    };
+                    self.write(seq, Some(&result));
+                }
+                return;
+            }
+            // there is nothing to print when only a missing part is wanted
+            Some(result) if self.key != Field::Root && self.key.value(result).is_empty() => None,
+            Some(result) if result.is_ip() && self.ips.is_some() => {
+                if let Some(ips) = &self.ips {
+                    ips.write(seq, Some(result));
+                }
+                None
+            }
+            Some(result) if result.unknown && self.unknown.is_some() => {
+                if let Some(unknown) = &self.unknown {
+                    unknown.write(seq, Some(result));
+                }
+                None
+            }
+            Some(result) => Some(result),
+            None => None,
+        };
+        let result = match (&self.seen, result) {
+            (Some(seen), Some(result)) if !seen.lock().unwrap().insert(key(self.key, result)) => {
+                None
+            }
+            (_, result) => result,
+        };
+        if result.is_none() && !self.ordered {
+            return;
+        }
+        if let Some(tx) = &*self.tx.read().unwrap() {
+            let _ = tx.send(Entry::Result(seq, result.cloned()));
+        }
+    }
+
+    /// Waits for the writer to get every result queued so far onto disk.
+    pub fn sync(&self) {
+        let tx = self.tx.read().unwrap().clone();
+        if let Some(tx) = tx {
+            let (synced, done) = mpsc::sync_channel(1);
+            if tx.send(Entry::Sync(synced)).is_ok() {
+                let _ = done.recv();
+            }
+        }
+        if let Some(unknown) = &self.unknown {
+            unknown.sync();
+        }
+        if let Some(ips) = &self.ips {
+            ips.sync();
+        }
+    }
+
+    /// Waits for the writer to write out every queued result and close off
+    /// the output.
+    pub fn finish(&self) {
+        self.tx.write().unwrap().take();
+        if let Some(thread) = self.thread.lock().unwrap().take() {
+            let _ = thread.join();
+        }
+        if let Some(unknown) = &self.unknown {
+            unknown.finish();
+        }
+        if let Some(ips) = &self.ips {
+            ips.finish();
+        }
+    }
+
+    /// The root domains first written since the last call, for adding to a
+    /// checkpoint. A bloom filter can not list them, so approximately unique
+    /// runs resume without.
+    pub fn fresh(&self) -> Vec<String> {
+        match self.seen.as_ref().map(|seen| seen.lock().unwrap()) {
+            Some(mut seen) => match &mut *seen {
+                Seen::Exact(_, Some(fresh)) => std::mem::take(fresh),
+                _ => vec![],
+            },
+            None => vec![],
+        }
+    }
+
+    /// Treats the root domains written by an earlier run as already seen.
+    pub fn remember(&self, roots: &[String]) {
+        if let Some(seen) = &self.seen {
+            // they are in the checkpoint already
+            if let Seen::Exact(seen, _) = &mut *seen.lock().unwrap() {
+                seen.extend(roots.iter().cloned());
+            }
+        }
+    }
+}
+
+/// What makes two results duplicates: the root domain, with the mailbox
+/// for email input and any port that is kept, or the only part that is
+/// printed.
+fn key(field: Field, result: &JobResult) -> String {
+    let key = match (field, &result.mailbox) {
+        (Field::Root, Some(mailbox)) => format!("{}@{}", mailbox, result.root()),
+        (field, _) => field.value(result),
+    };
+    match (field, &result.port) {
+        (Field::Root, Some(port)) => format!("{}:{}", key, port),
+        _ => key,
+    }
+}
+
+/// Stops the run when results can no longer be written. A closed pipe, e.g.
+/// into `head`, is not worth a message.
+fn fail(e: io::Error) -> ! {
+    if e.kind() != io::ErrorKind::BrokenPipe {
+        eprintln!("could not write output: {}", e);
+    }
+    std::process::exit(1);
+}
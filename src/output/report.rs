@@ -0,0 +1,75 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::JobResult;
+
+#[derive(Default)]
+struct Root {
+    suffix: String,
+    hosts: u64,
+    subdomains: HashSet<String>,
+}
+
+/// Tallies the results for a markdown report of the root domains and
+/// suffixes they span.
+#[derive(Default)]
+pub struct Report {
+    hosts: u64,
+    roots: HashMap<String, Root>,
+}
+
+impl Report {
+    pub fn add(&mut self, result: &JobResult) {
+        self.hosts += 1;
+        let root = self.roots.entry(result.root()).or_default();
+        if root.suffix.is_empty() {
+            root.suffix = result.suffix.clone();
+        }
+        root.hosts += 1;
+        if !result.subdomain.is_empty() {
+            root.subdomains.insert(result.subdomain.clone());
+        }
+    }
+
+    /// Writes the report out, the biggest root domains and suffixes first.
+    pub fn markdown(&self) -> String {
+        let mut suffixes: HashMap<&str, (u64, u64)> = HashMap::new();
+        for root in self.roots.values() {
+            let suffix = suffixes.entry(&root.suffix).or_default();
+            suffix.0 += root.hosts;
+            suffix.1 += 1;
+        }
+
+        let mut report = String::from("# hostparser report\n\n");
+        report += "| | |\n|---|---:|\n";
+        report += &format!("| Hosts | {} |\n", self.hosts);
+        report += &format!("| Root domains | {} |\n", self.roots.len());
+        report += &format!("| Suffixes | {} |\n", suffixes.len());
+
+        let mut roots: Vec<_> = self.roots.iter().collect();
+        roots.sort_by(|a, b| b.1.hosts.cmp(&a.1.hosts).then_with(|| a.0.cmp(b.0)));
+        report += "\n## Root domains\n\n";
+        report += "| Root domain | Hosts | Subdomains |\n|---|---:|---:|\n";
+        for (name, root) in roots {
+            report += &format!(
+                "| {} | {} | {} |\n",
+                escape(name),
+                root.hosts,
+                root.subdomains.len()
+            );
+        }
+
+        let mut suffixes: Vec<_> = suffixes.into_iter().collect();
+        suffixes.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then_with(|| a.0.cmp(b.0)));
+        report += "\n## Suffixes\n\n";
+        report += "| Suffix | Hosts | Root domains |\n|---|---:|---:|\n";
+        for (suffix, (hosts, roots)) in suffixes {
+            report += &format!("| {} | {} | {} |\n", escape(suffix), hosts, roots);
+        }
+        report
+    }
+}
+
+/// Keeps a name from breaking out of its table cell.
+fn escape(name: &str) -> String {
+    name.replace('|', "\\|")
+}
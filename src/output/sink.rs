@@ -0,0 +1,474 @@
+use flate2::write::GzEncoder;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tempfile::TempPath;
+
+use super::elastic::Elastic;
+use super::sqlite::Store;
+use super::syslog::Syslog;
+use super::webhook::Webhook;
+use super::{fail, Compression, Field, OutputFormat, OutputOptions};
+use crate::JobResult;
+
+/// Size of the buffer in front of every file and stdout, so results go out
+/// in large writes rather than a line at a time.
+const BUFFER_SIZE: usize = 256 * 1024;
+
+/// Size of the buffer in front of each file results are split into, small
+/// as many of them are open at once.
+const SPLIT_BUFFER_SIZE: usize = 8 * 1024;
+
+/// How many of the files results are split into are open at once. The one
+/// written to least recently is closed to make room, and reopened to add
+/// to it when another of its results comes in.
+const MAX_OPEN_FILES: usize = 128;
+
+/// One of the places the results are written to.
+pub enum Sink {
+    /// Stdout, or a file written next to its path and only moved into place
+    /// once the run is done, so an interrupted run never leaves a truncated
+    /// file behind. Checkpointed runs write in place instead, so a resumed
+    /// run can carry on with what was written, and so do paths that are not
+    /// plain files, such as pipes and devices.
+    Stream {
+        writer: Box<dyn Write + Send>,
+        tmp: Option<(TempPath, String)>,
+        /// The file underneath the writer, for syncing it to disk.
+        file: Option<Arc<File>>,
+        color: bool,
+        /// Ends every line with a NUL byte instead of a newline.
+        print0: bool,
+    },
+    Sqlite(Store),
+    Webhook(Webhook),
+    Elastic(Elastic),
+    Syslog(Syslog),
+    /// A file per suffix or root domain in a directory, each opened when
+    /// its first result comes in.
+    Split {
+        field: Field,
+        dir: PathBuf,
+        options: Box<OutputOptions>,
+        /// The files that are open, with when each was last written to.
+        files: HashMap<String, (Sink, u64)>,
+        /// Every suffix or root domain a file was started for.
+        keys: HashSet<String>,
+        /// The temporary files of the files closed to make room, to go on
+        /// with when they are reopened.
+        parked: HashMap<String, TempPath>,
+        /// How many results have been written, to tell when each file was.
+        written: u64,
+    },
+}
+
+impl Sink {
+    /// Opens `-` as stdout, a `sqlite://` database, or any other path as a
+    /// file. Files are created up front so an unwritable path fails before
+    /// any work is done.
+    pub fn open(
+        path: &str,
+        options: &OutputOptions,
+    ) -> Result<Sink, Box<dyn Error + Send + Sync + 'static>> {
+        if path == "-" {
+            let mut writer = compress(io::stdout(), options.compression, BUFFER_SIZE)?;
+            writer.write_all(options.format.header().as_bytes())?;
+            return Ok(Sink::Stream {
+                writer,
+                tmp: None,
+                file: None,
+                color: options.color && options.compression.is_none() && io::stdout().is_terminal(),
+                print0: options.print0,
+            });
+        }
+        if let Some(path) = path.strip_prefix("sqlite://") {
+            if options.count || options.tree || options.report {
+                return Err("--count, --tree and --report cannot be written to sqlite".into());
+            }
+            return match Store::open(path) {
+                Ok(store) => Ok(Sink::Sqlite(store)),
+                Err(e) => Err(format!("could not open output {}: {}", path, e).into()),
+            };
+        }
+
+        Sink::file(path, options, false, None, BUFFER_SIZE)
+    }
+
+    /// Opens the file at `path`, or with `reopen` goes on with one this run
+    /// started and closed again, along with its temporary file if it had one.
+    fn file(
+        path: &str,
+        options: &OutputOptions,
+        reopen: bool,
+        tmp: Option<TempPath>,
+        capacity: usize,
+    ) -> Result<Sink, Box<dyn Error + Send + Sync + 'static>> {
+        let created = match tmp {
+            Some(tmp) => open(&tmp, true).map(|file| (file, Some(tmp))),
+            None if reopen || options.in_place || !replaceable(path) => {
+                open(Path::new(path), options.append || reopen).map(|file| (file, None))
+            }
+            None => create(path, options.append).map(|(file, tmp)| (file, Some(tmp))),
+        };
+        let (file, tmp) = match created {
+            Ok(created) => created,
+            Err(e) => return Err(format!("could not create output {}: {}", path, e).into()),
+        };
+        // an appended file already starts with its header
+        let header = file.metadata()?.len() == 0;
+        let compression = options.compression.or_else(|| Compression::for_path(path));
+        let file = Arc::new(file);
+        let mut writer = compress(Shared(file.clone()), compression, capacity)?;
+        if header {
+            writer.write_all(options.format.header().as_bytes())?;
+        }
+        Ok(Sink::Stream {
+            writer,
+            tmp: tmp.map(|tmp| (tmp, path.to_string())),
+            file: Some(file),
+            color: false,
+            print0: options.print0,
+        })
+    }
+
+    /// Opens the directory that results are split into by `field`.
+    pub fn split(
+        field: Field,
+        dir: &str,
+        options: &OutputOptions,
+    ) -> Result<Sink, Box<dyn Error + Send + Sync + 'static>> {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            return Err(format!("could not create output directory {}: {}", dir, e).into());
+        }
+        Ok(Sink::Split {
+            field,
+            dir: dir.into(),
+            options: Box::new(options.clone()),
+            files: HashMap::new(),
+            keys: HashSet::new(),
+            parked: HashMap::new(),
+            written: 0,
+        })
+    }
+
+    /// Writes a single result, `first` telling whether it is the first one.
+    pub fn record(&mut self, format: &OutputFormat, result: &JobResult, first: bool) {
+        match self {
+            Sink::Split {
+                field,
+                dir,
+                options,
+                files,
+                keys,
+                parked,
+                written,
+            } => {
+                let key = field.value(result);
+                let first = !keys.contains(&key);
+                if !files.contains_key(&key) {
+                    if files.len() >= MAX_OPEN_FILES {
+                        let oldest = files
+                            .iter()
+                            .min_by_key(|(_, (_, used))| *used)
+                            .map(|(key, _)| key.clone());
+                        if let Some((oldest, (mut sink, _))) =
+                            oldest.and_then(|key| files.remove_entry(&key))
+                        {
+                            // a checkpoint may cover what went into it
+                            match options.in_place {
+                                true => sink.sync(),
+                                false => sink.flush(),
+                            }
+                            if let Sink::Stream {
+                                tmp: Some((tmp, _)),
+                                ..
+                            } = sink
+                            {
+                                parked.insert(oldest, tmp);
+                            }
+                        }
+                    }
+                    let path = dir.join(file_name(&key, options.compression));
+                    let tmp = parked.remove(&key);
+                    match Sink::file(
+                        &path.to_string_lossy(),
+                        options,
+                        !first,
+                        tmp,
+                        SPLIT_BUFFER_SIZE,
+                    ) {
+                        Ok(sink) => files.insert(key.clone(), (sink, 0)),
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            std::process::exit(1);
+                        }
+                    };
+                    keys.insert(key.clone());
+                }
+                *written += 1;
+                if let Some((sink, used)) = files.get_mut(&key) {
+                    *used = *written;
+                    sink.record(format, result, first);
+                }
+            }
+            Sink::Stream {
+                writer,
+                color,
+                print0,
+                ..
+            } => {
+                let mut record = format.record(result, first, *color);
+                if *print0 && record.ends_with('\n') {
+                    record.pop();
+                    record.push('\0');
+                }
+                if let Err(e) = writer.write_all(record.as_bytes()) {
+                    fail(e);
+                }
+            }
+            Sink::Webhook(webhook) => webhook.send(result.root()),
+            Sink::Elastic(elastic) => elastic.send(result),
+            Sink::Syslog(syslog) => {
+                if let Err(e) = syslog.send(result) {
+                    fail(e);
+                }
+            }
+            Sink::Sqlite(store) => {
+                if let Err(e) = store.upsert(result) {
+                    eprintln!("could not write output: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
+    /// Writes text that is not a result, such as counts.
+    pub fn write_all(&mut self, data: &[u8]) {
+        if let Sink::Stream { writer, print0, .. } = self {
+            let written = if *print0 {
+                let data: Vec<u8> = data
+                    .iter()
+                    .map(|b| if *b == b'\n' { 0 } else { *b })
+                    .collect();
+                writer.write_all(&data)
+            } else {
+                writer.write_all(data)
+            };
+            if let Err(e) = written {
+                fail(e);
+            }
+        }
+    }
+
+    /// Pushes out what is buffered, while there is nothing else to write.
+    pub fn flush(&mut self) {
+        match self {
+            Sink::Stream { writer, .. } => {
+                if let Err(e) = writer.flush() {
+                    fail(e);
+                }
+            }
+            Sink::Split { files, .. } => {
+                for (sink, _) in files.values_mut() {
+                    sink.flush();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Flushes what is buffered and waits for the files to reach the disk,
+    /// so a saved checkpoint never gets ahead of the output.
+    pub fn sync(&mut self) {
+        match self {
+            Sink::Stream { writer, file, .. } => {
+                let synced = writer
+                    .flush()
+                    .and_then(|_| file.as_ref().map_or(Ok(()), |file| file.sync_data()));
+                if let Err(e) = synced {
+                    fail(e);
+                }
+            }
+            Sink::Split { files, .. } => {
+                for (sink, _) in files.values_mut() {
+                    sink.sync();
+                }
+            }
+            Sink::Sqlite(store) => {
+                if let Err(e) = store.flush() {
+                    eprintln!("could not write output: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Writes the footer and puts the output in place.
+    pub fn close(&mut self, footer: &str) {
+        match self {
+            Sink::Stream {
+                writer, tmp, file, ..
+            } => {
+                if let Err(e) = writer
+                    .write_all(footer.as_bytes())
+                    .and_then(|_| writer.flush())
+                {
+                    fail(e);
+                }
+                // closing the file finishes off any compressed stream
+                *writer = Box::new(io::sink());
+                *file = None;
+                if let Some((tmp, path)) = tmp.take() {
+                    if let Err(e) = tmp.persist(&path) {
+                        eprintln!("could not write output {}: {}", path, e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            Sink::Sqlite(store) => {
+                if let Err(e) = store.commit() {
+                    eprintln!("could not write output: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            Sink::Webhook(webhook) => webhook.close(),
+            Sink::Elastic(elastic) => elastic.close(),
+            Sink::Syslog(_) => {}
+            Sink::Split {
+                dir,
+                options,
+                files,
+                keys,
+                parked,
+                ..
+            } => {
+                // every file holds at least one result
+                let footer = options.format.footer(false);
+                for key in keys.iter() {
+                    let mut sink = match files.remove(key) {
+                        Some((sink, _)) => sink,
+                        None => {
+                            let path = dir.join(file_name(key, options.compression));
+                            match Sink::file(
+                                &path.to_string_lossy(),
+                                options,
+                                true,
+                                parked.remove(key),
+                                SPLIT_BUFFER_SIZE,
+                            ) {
+                                Ok(sink) => sink,
+                                Err(e) => {
+                                    eprintln!("{}", e);
+                                    std::process::exit(1);
+                                }
+                            }
+                        }
+                    };
+                    sink.close(&footer);
+                }
+            }
+        }
+    }
+}
+
+/// A file written through the writer in front of it while the sink holds
+/// on to it for syncing, without taking up a second descriptor.
+struct Shared(Arc<File>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self.0).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self.0).flush()
+    }
+}
+
+/// The name of the file a suffix or root domain is split into, keeping only
+/// characters that are safe in a file name.
+fn file_name(key: &str, compression: Option<Compression>) -> String {
+    let mut name: String = key
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect();
+    if name.starts_with('.') {
+        name.insert(0, '_');
+    }
+    match compression {
+        Some(Compression::Gzip) => name + ".gz",
+        Some(Compression::Zstd) => name + ".zst",
+        None => name,
+    }
+}
+
+/// Wraps the writer in an encoder for the compression, buffering up to
+/// `capacity` bytes of what is written to it.
+fn compress<W: Write + Send + 'static>(
+    writer: W,
+    compression: Option<Compression>,
+    capacity: usize,
+) -> io::Result<Box<dyn Write + Send>> {
+    Ok(match compression {
+        Some(Compression::Gzip) => Box::new(BufWriter::with_capacity(
+            capacity,
+            GzEncoder::new(writer, flate2::Compression::default()),
+        )),
+        Some(Compression::Zstd) => Box::new(BufWriter::with_capacity(
+            capacity,
+            zstd::Encoder::new(writer, 0)?.auto_finish(),
+        )),
+        None => Box::new(BufWriter::with_capacity(capacity, writer)),
+    })
+}
+
+/// Whether the output at `path` can be written to a temporary file and
+/// moved into place: a plain file or one that does not exist yet, but not
+/// a pipe, a device or a link to one.
+fn replaceable(path: &str) -> bool {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata.is_file(),
+        Err(_) => true,
+    }
+}
+
+/// Creates a temporary file next to the output at `path`, starting from a
+/// copy of the current output when appending to it.
+fn create(path: &str, append: bool) -> io::Result<(File, TempPath)> {
+    let dir = match Path::new(path).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(".hostparser");
+    // the output is as readable as any other file the user creates
+    #[cfg(unix)]
+    builder.permissions(std::os::unix::fs::PermissionsExt::from_mode(0o666));
+    let mut tmp = builder.tempfile_in(dir)?;
+    if append {
+        match File::open(path) {
+            Ok(mut current) => {
+                io::copy(&mut current, tmp.as_file_mut())?;
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(tmp.into_parts())
+}
+
+/// Opens the output at `path` to be written in place, adding to its end
+/// when appending to it.
+fn open(path: &Path, append: bool) -> io::Result<File> {
+    match append {
+        true => OpenOptions::new().create(true).append(true).open(path),
+        false => File::create(path),
+    }
+}
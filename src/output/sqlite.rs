@@ -0,0 +1,68 @@
+use rusqlite::{params, Connection};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::JobResult;
+
+/// How many results go into one transaction.
+const BATCH_SIZE: usize = 1000;
+
+/// A SQLite database the results are upserted into, keeping when each host
+/// was first and last seen across runs.
+pub struct Store {
+    conn: Connection,
+    pending: usize,
+}
+
+impl Store {
+    pub fn open(path: &str) -> rusqlite::Result<Store> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS results (
+                input TEXT NOT NULL PRIMARY KEY,
+                root TEXT NOT NULL,
+                suffix TEXT NOT NULL,
+                first_seen INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL
+            );
+            BEGIN;",
+        )?;
+        Ok(Store { conn, pending: 0 })
+    }
+
+    pub fn upsert(&mut self, result: &JobResult) -> rusqlite::Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| now.as_secs())
+            .unwrap_or_default();
+        self.conn
+            .prepare_cached(
+                "INSERT INTO results (input, root, suffix, first_seen, last_seen)
+                VALUES (?1, ?2, ?3, ?4, ?4)
+                ON CONFLICT (input) DO UPDATE SET
+                    root = excluded.root,
+                    suffix = excluded.suffix,
+                    last_seen = excluded.last_seen",
+            )?
+            .execute(params![result.input, result.root(), result.suffix, now])?;
+
+        self.pending += 1;
+        if self.pending >= BATCH_SIZE {
+            self.pending = 0;
+            self.conn.execute_batch("COMMIT; BEGIN;")?;
+        }
+        Ok(())
+    }
+
+    /// Commits the results that are pending and carries on in a new
+    /// transaction.
+    pub fn flush(&mut self) -> rusqlite::Result<()> {
+        self.pending = 0;
+        self.conn.execute_batch("COMMIT; BEGIN;")
+    }
+
+    /// Commits the results that are still pending.
+    pub fn commit(&mut self) -> rusqlite::Result<()> {
+        self.pending = 0;
+        self.conn.execute_batch("COMMIT;")
+    }
+}
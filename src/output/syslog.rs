@@ -0,0 +1,108 @@
+use std::io::{self, Write};
+use std::net::{TcpStream, UdpSocket};
+use std::os::unix::net::UnixDatagram;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::JobResult;
+
+/// The user facility at the informational severity.
+const PRIORITY: u8 = 14;
+
+enum Transport {
+    Udp(UdpSocket),
+    /// Messages are framed by their length, as RFC 6587 describes.
+    Tcp(TcpStream),
+    Unix(UnixDatagram),
+}
+
+/// Sends each root domain as an RFC 5424 syslog message.
+pub struct Syslog {
+    transport: Transport,
+    hostname: String,
+    pid: u32,
+}
+
+impl Syslog {
+    /// Connects to a `udp://host:port`, `tcp://host:port` or `unix:///path`
+    /// syslog server.
+    pub fn connect(url: &str) -> Result<Syslog, String> {
+        let transport = match url.split_once("://") {
+            Some(("udp", addr)) => UdpSocket::bind("0.0.0.0:0")
+                .and_then(|socket| socket.connect(addr).map(|_| socket))
+                .map(Transport::Udp),
+            Some(("tcp", addr)) => TcpStream::connect(addr).map(Transport::Tcp),
+            Some(("unix", path)) => UnixDatagram::unbound()
+                .and_then(|socket| socket.connect(path).map(|_| socket))
+                .map(Transport::Unix),
+            _ => {
+                return Err(format!(
+                    "unknown syslog address {}, use udp://, tcp:// or unix://",
+                    url
+                ))
+            }
+        };
+        let transport = match transport {
+            Ok(transport) => transport,
+            Err(e) => return Err(format!("could not connect to syslog {}: {}", url, e)),
+        };
+        let hostname = std::fs::read_to_string("/proc/sys/kernel/hostname")
+            .map(|name| name.trim().to_string())
+            .unwrap_or_default();
+        Ok(Syslog {
+            transport,
+            hostname: if hostname.is_empty() {
+                "-".to_string()
+            } else {
+                hostname
+            },
+            pid: std::process::id(),
+        })
+    }
+
+    pub fn send(&mut self, result: &JobResult) -> io::Result<()> {
+        let message = format!(
+            "<{}>1 {} {} hostparser {} - - {}",
+            PRIORITY,
+            timestamp(),
+            self.hostname,
+            self.pid,
+            result.root()
+        );
+        match &mut self.transport {
+            Transport::Udp(socket) => socket.send(message.as_bytes()).map(|_| ()),
+            Transport::Tcp(stream) => write!(stream, "{} {}", message.len(), message),
+            Transport::Unix(socket) => socket.send(message.as_bytes()).map(|_| ()),
+        }
+    }
+}
+
+/// The current time in UTC as an RFC 3339 timestamp.
+fn timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = now.as_secs();
+    let (days, rest) = (secs / 86400, secs % 86400);
+
+    // days since the epoch to a civil date, after Howard Hinnant's algorithm
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rest / 3600,
+        rest % 3600 / 60,
+        rest % 60,
+        now.subsec_millis()
+    )
+}
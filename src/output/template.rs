@@ -0,0 +1,274 @@
+use crate::JobResult;
+
+/// A part of a result that can be printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Field {
+    Input,
+    #[default]
+    Root,
+    Subdomain,
+    Domain,
+    Suffix,
+    Mailbox,
+    Sources,
+    Port,
+    Wildcard,
+    Host,
+    Depth,
+    Entropy,
+    Ngram,
+    Dga,
+    Squat,
+    Reversed,
+    Surt,
+}
+
+impl Field {
+    pub fn new(name: &str) -> Result<Field, String> {
+        match name {
+            "input" => Ok(Field::Input),
+            "root" => Ok(Field::Root),
+            "subdomain" => Ok(Field::Subdomain),
+            "domain" => Ok(Field::Domain),
+            "suffix" => Ok(Field::Suffix),
+            "mailbox" => Ok(Field::Mailbox),
+            "sources" => Ok(Field::Sources),
+            "port" => Ok(Field::Port),
+            "wildcard" => Ok(Field::Wildcard),
+            "host" => Ok(Field::Host),
+            "depth" => Ok(Field::Depth),
+            "entropy" => Ok(Field::Entropy),
+            "ngram" => Ok(Field::Ngram),
+            "dga" => Ok(Field::Dga),
+            "squat" => Ok(Field::Squat),
+            "reversed" => Ok(Field::Reversed),
+            "surt" => Ok(Field::Surt),
+            _ => Err(format!("unknown field {}", name)),
+        }
+    }
+
+    /// The value of the field for a result.
+    pub fn value(&self, result: &JobResult) -> String {
+        match self {
+            Field::Input => result.input.clone(),
+            Field::Root => result.root(),
+            Field::Subdomain => result.subdomain.clone(),
+            Field::Domain => result.domain.clone(),
+            Field::Suffix => result.suffix.clone(),
+            Field::Mailbox => result.mailbox.clone().unwrap_or_default(),
+            Field::Sources => result.sources.join(","),
+            Field::Port => result.port.clone().unwrap_or_default(),
+            Field::Wildcard if result.wildcard => "wildcard".to_string(),
+            Field::Wildcard => String::new(),
+            Field::Host => result.host(),
+            Field::Depth => result.depth().to_string(),
+            Field::Entropy => format!("{:.2}", result.score().entropy),
+            Field::Ngram => format!("{:.2}", result.score().ngram),
+            Field::Dga if result.dga => "dga".to_string(),
+            Field::Dga => String::new(),
+            Field::Squat => result.squat.clone().unwrap_or_default(),
+            Field::Reversed => result
+                .host()
+                .trim_end_matches('.')
+                .rsplit('.')
+                .collect::<Vec<_>>()
+                .join("."),
+            Field::Surt => surt(result),
+        }
+    }
+
+    /// The value of the field with the subdomain, domain and suffix each in
+    /// their own terminal color.
+    pub fn paint(&self, result: &JobResult) -> String {
+        match self {
+            // hosts are split up, anything else such as urls left as it is
+            Field::Input if result.input == result.host() && !result.subdomain.is_empty() => {
+                format!(
+                    "{}.{}",
+                    Field::Subdomain.paint(result),
+                    Field::Root.paint(result)
+                )
+            }
+            Field::Input if result.input == result.host() => Field::Root.paint(result),
+            Field::Host if !result.subdomain.is_empty() => format!(
+                "{}.{}",
+                Field::Subdomain.paint(result),
+                Field::Root.paint(result)
+            ),
+            Field::Host => Field::Root.paint(result),
+            Field::Root => {
+                let dot = if result.fqdn { "." } else { "" };
+                if result.is_whole() {
+                    return format!("{}{}", Field::Domain.paint(result), dot);
+                }
+                format!(
+                    "{}.{}{}",
+                    Field::Domain.paint(result),
+                    Field::Suffix.paint(result),
+                    dot
+                )
+            }
+            Field::Subdomain => color(SUBDOMAIN, &result.subdomain),
+            Field::Domain => color(DOMAIN, &result.domain),
+            Field::Suffix => color(SUFFIX, &result.suffix),
+            _ => self.value(result),
+        }
+    }
+}
+
+/// The ansi colors of the parts of a host.
+const SUBDOMAIN: &str = "36";
+const DOMAIN: &str = "1;32";
+const SUFFIX: &str = "33";
+
+/// The SURT key of a result as web archives canonicalize it: the labels of
+/// the host reversed and comma separated without a leading `www`, any port
+/// but the default ones, and the lowercased path and sorted query of the
+/// input url, such as `com,example:8080)/path?a=1&b=2`.
+fn surt(result: &JobResult) -> String {
+    let host = result.host();
+    let host = host.trim_end_matches('.');
+    let mut key = if result.is_ip() {
+        host.to_string()
+    } else {
+        let mut labels: Vec<&str> = host.split('.').collect();
+        let www = |label: &str| {
+            label
+                .strip_prefix("www")
+                .is_some_and(|rest| rest.chars().all(|c| c.is_ascii_digit()))
+        };
+        if labels.len() > 2 && www(labels[0]) {
+            labels.remove(0);
+        }
+        labels.reverse();
+        labels.join(",")
+    };
+    match result.port.as_deref() {
+        Some("80") | Some("443") | None => {}
+        Some(port) => key = format!("{}:{}", key, port),
+    }
+    format!("{}){}", key, path(&result.input))
+}
+
+/// The path and query of a url, `/` when it has neither.
+fn path(input: &str) -> String {
+    let input = input.trim();
+    let rest = match input.split_once("://") {
+        Some((_, rest)) => rest,
+        None => input.strip_prefix("//").unwrap_or(input),
+    };
+    let rest = rest.split('#').next().unwrap_or_default();
+    let (path, query) = match rest.find(['/', '?']) {
+        Some(start) => {
+            let target = &rest[start..];
+            match target.split_once('?') {
+                Some((path, query)) => (path, Some(query)),
+                None => (target, None),
+            }
+        }
+        None => ("", None),
+    };
+    let mut path = match path {
+        "" => "/".to_string(),
+        path => path.to_lowercase(),
+    };
+    if let Some(query) = query.filter(|query| !query.is_empty()) {
+        let mut params: Vec<String> = query.split('&').map(|p| p.to_lowercase()).collect();
+        params.sort();
+        path.push('?');
+        path.push_str(&params.join("&"));
+    }
+    path
+}
+
+fn color(code: &str, text: &str) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Literal(String),
+    Field(Field),
+}
+
+/// A `--format` template such as `{domain}.{suffix} {input}`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Parses a template. `{{` and `}}` stand for literal braces and `\t`
+    /// and `\n` for a tab and a newline.
+    pub fn new(template: &str) -> Result<Template, String> {
+        let template = template.replace("\\t", "\t").replace("\\n", "\n");
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        name.push(c);
+                    }
+                    if !closed {
+                        return Err(format!("unterminated field {{{}", name));
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(Field::new(name.trim())?));
+                }
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Template { parts })
+    }
+
+    /// Builds a template printing a comma separated list of fields, such as
+    /// `root,subdomain`, joined by `separator`.
+    pub fn from_fields(fields: &str, separator: &str) -> Result<Template, String> {
+        let separator = separator.replace("\\t", "\t");
+        let mut parts = Vec::new();
+        for (i, name) in fields.split(',').enumerate() {
+            if i > 0 {
+                parts.push(Part::Literal(separator.clone()));
+            }
+            parts.push(Part::Field(Field::new(name.trim())?));
+        }
+        Ok(Template { parts })
+    }
+
+    /// Fills the template in for a result, in color when `color` is set.
+    pub fn render(&self, result: &JobResult, color: bool) -> String {
+        let mut line = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => line.push_str(text),
+                Part::Field(field) if color => line.push_str(&field.paint(result)),
+                Part::Field(field) => line.push_str(&field.value(result)),
+            }
+        }
+        line
+    }
+}
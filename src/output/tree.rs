@@ -0,0 +1,27 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// The subdomains seen under a root domain, one level per label.
+#[derive(Debug, Default)]
+pub struct Tree {
+    children: BTreeMap<String, Tree>,
+}
+
+impl Tree {
+    /// Adds a subdomain such as `dev.api`, walking its labels from the right.
+    pub fn insert(&mut self, subdomain: &str) {
+        let mut node = self;
+        for label in subdomain.rsplit('.').filter(|label| !label.is_empty()) {
+            node = node.children.entry(label.to_string()).or_default();
+        }
+    }
+
+    /// Writes the labels below this node, indented two spaces per level.
+    pub fn write(&self, writer: &mut dyn Write, depth: usize) -> io::Result<()> {
+        for (label, child) in &self.children {
+            writeln!(writer, "{:indent$}{}", "", label, indent = depth * 2)?;
+            child.write(writer, depth + 1)?;
+        }
+        Ok(())
+    }
+}
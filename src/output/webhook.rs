@@ -0,0 +1,55 @@
+use reqwest::blocking::Client;
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::mpsc::SyncSender;
+use std::thread::JoinHandle;
+
+use super::batch;
+
+/// How many roots are sent in one request at most.
+const BATCH_SIZE: usize = 100;
+
+/// Posts every root domain the first time it is seen to a url, batched as
+/// `{"roots": [...]}`. Requests are made on a thread of their own so a slow
+/// endpoint never holds up the output.
+pub struct Webhook {
+    tx: Option<SyncSender<String>>,
+    thread: Option<JoinHandle<()>>,
+    seen: HashSet<String>,
+}
+
+impl Webhook {
+    pub fn new(url: &str) -> Webhook {
+        let url = url.to_string();
+        // the blocking client has to be made off the async runtime
+        let mut client = None;
+        let (tx, thread) = batch::spawn(BATCH_SIZE, 10 * BATCH_SIZE, move |roots: Vec<String>| {
+            let client = client.get_or_insert_with(Client::new);
+            let body = json!({ "roots": roots }).to_string();
+            batch::post(client, &url, "application/json", body);
+        });
+        Webhook {
+            tx: Some(tx),
+            thread: Some(thread),
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Queues a root domain unless it has been sent already.
+    pub fn send(&mut self, root: String) {
+        if !self.seen.insert(root.clone()) {
+            return;
+        }
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(root);
+        }
+    }
+
+    /// Sends what is still queued and waits for it to be delivered.
+    pub fn close(&mut self) {
+        self.tx = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use clap::ArgMatches;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, IsTerminal, Write};
+use tldextract::TldExtractor;
+
+use crate::normalize;
+
+/// Words that environments and services are commonly named after, used
+/// when no word list is given.
+const WORDS: [&str; 32] = [
+    "dev",
+    "development",
+    "stage",
+    "staging",
+    "test",
+    "testing",
+    "qa",
+    "uat",
+    "prod",
+    "production",
+    "preprod",
+    "sandbox",
+    "demo",
+    "beta",
+    "alpha",
+    "internal",
+    "corp",
+    "admin",
+    "api",
+    "app",
+    "portal",
+    "vpn",
+    "mail",
+    "static",
+    "cdn",
+    "old",
+    "new",
+    "backup",
+    "v1",
+    "v2",
+    "int",
+    "ext",
+];
+
+/// Prints the candidate hosts for every host of the `permute` subcommand,
+/// splitting hosts with the same suffix list as a parsing run.
+pub fn run(
+    matches: &ArgMatches,
+    extractor: &TldExtractor,
+) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    let words: Vec<String> = match matches.value_of("wordlist") {
+        Some(path) => match std::fs::read_to_string(path) {
+            Ok(list) => list
+                .lines()
+                .map(|word| word.trim().to_lowercase())
+                .filter(|word| !word.is_empty() && !word.starts_with('#'))
+                .collect(),
+            Err(e) => return Err(format!("could not read {}: {}", path, e).into()),
+        },
+        None => WORDS.iter().map(|word| word.to_string()).collect(),
+    };
+
+    // hosts are altered as they are read, so a long list is never held whole
+    let hosts: Box<dyn Iterator<Item = String> + '_> =
+        if let Some(hosts) = matches.values_of("hosts") {
+            Box::new(hosts.map(|host| host.to_string()))
+        } else if let Some(path) = matches.value_of("file") {
+            match File::open(path) {
+                Ok(file) => Box::new(BufReader::new(file).lines().map_while(Result::ok)),
+                Err(e) => return Err(format!("could not read {}: {}", path, e).into()),
+            }
+        } else if std::io::stdin().is_terminal() {
+            return Err("no hosts to permute, give them as arguments, with -f or on stdin".into());
+        } else {
+            Box::new(std::io::stdin().lock().lines().map_while(Result::ok))
+        };
+
+    let mut seen = HashSet::new();
+    let mut out = BufWriter::new(std::io::stdout().lock());
+    for host in hosts {
+        let tidy = normalize::tidy(&host, true);
+        let (host, _) = normalize::host(&tidy);
+        let (host, _) = normalize::wildcard(host);
+        let (host, _) = normalize::trailing_dot(host);
+        if host.is_empty() {
+            continue;
+        }
+        let parts = match extractor.extract(host) {
+            Ok(parts) => parts,
+            Err(_) => continue,
+        };
+        let (domain, suffix) = match (parts.domain, parts.suffix) {
+            (Some(domain), Some(suffix)) => (domain, suffix),
+            _ => continue,
+        };
+        let root = format!("{}.{}", domain, suffix);
+        let subdomain = parts.subdomain.unwrap_or_default();
+        seen.insert(host.to_string());
+        for candidate in permutations(&subdomain, &root, &words) {
+            if seen.insert(candidate.clone()) {
+                writeln!(out, "{}", candidate)?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// The dnsgen and altdns style alterations of a host, given as its
+/// subdomain and root domain: every word inserted as a label of its own,
+/// joined onto each label with and without a hyphen, and the numbers in
+/// each label counted one up and down.
+pub fn permutations(subdomain: &str, root: &str, words: &[String]) -> Vec<String> {
+    let labels: Vec<&str> = subdomain.split('.').filter(|l| !l.is_empty()).collect();
+    let join = |labels: &[String]| match labels.is_empty() {
+        true => root.to_string(),
+        false => format!("{}.{}", labels.join("."), root),
+    };
+    let mut candidates = vec![];
+
+    for word in words {
+        for i in 0..=labels.len() {
+            let mut altered: Vec<String> = labels.iter().map(|l| l.to_string()).collect();
+            altered.insert(i, word.clone());
+            candidates.push(join(&altered));
+        }
+        for i in 0..labels.len() {
+            for label in [
+                format!("{}-{}", word, labels[i]),
+                format!("{}-{}", labels[i], word),
+                format!("{}{}", word, labels[i]),
+                format!("{}{}", labels[i], word),
+            ] {
+                let mut altered: Vec<String> = labels.iter().map(|l| l.to_string()).collect();
+                altered[i] = label;
+                candidates.push(join(&altered));
+            }
+        }
+    }
+
+    for i in 0..labels.len() {
+        for label in increments(labels[i]) {
+            let mut altered: Vec<String> = labels.iter().map(|l| l.to_string()).collect();
+            altered[i] = label;
+            candidates.push(join(&altered));
+        }
+    }
+    candidates
+}
+
+/// The label with its first number counted one up and, above zero, one
+/// down, keeping any leading zeros: `web01` gives `web02` and `web00`.
+fn increments(label: &str) -> Vec<String> {
+    let start = match label.find(|c: char| c.is_ascii_digit()) {
+        Some(start) => start,
+        None => return vec![],
+    };
+    let end = label[start..]
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or(label.len(), |end| start + end);
+    let digits = &label[start..end];
+    let number = match digits.parse::<u64>() {
+        Ok(number) => number,
+        Err(_) => return vec![],
+    };
+    let mut numbers = vec![number + 1];
+    if number > 0 {
+        numbers.push(number - 1);
+    }
+    numbers
+        .into_iter()
+        .map(|n| {
+            format!(
+                "{}{:0width$}{}",
+                &label[..start],
+                n,
+                &label[end..],
+                width = digits.len()
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,127 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::stats::Stats;
+
+/// How often the bar is redrawn.
+const INTERVAL: Duration = Duration::from_millis(500);
+
+/// Width of the bar itself, in characters.
+const WIDTH: usize = 30;
+
+/// A progress bar on stderr for inputs of a known size, redrawn on a thread
+/// of its own.
+pub struct Progress {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Progress {
+    /// Starts drawing the progress through `total` bytes of input.
+    pub fn start(stats: Arc<Stats>, total: u64) -> Progress {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let thread = std::thread::spawn(move || {
+            let start = Instant::now();
+            let mut last = (Instant::now(), 0);
+            while !stopped.load(Ordering::Relaxed) {
+                std::thread::sleep(INTERVAL);
+                let parsed = stats.parsed.load(Ordering::Relaxed);
+                let rate = (parsed - last.1) as f64 / last.0.elapsed().as_secs_f64();
+                last = (Instant::now(), parsed);
+                let line = draw(&stats, total, start.elapsed(), rate);
+                let mut stderr = std::io::stderr().lock();
+                let _ = write!(stderr, "\r\x1b[K{}", line);
+                let _ = stderr.flush();
+            }
+            eprint!("\r\x1b[K");
+        });
+        Progress {
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    /// Takes the bar off the screen.
+    pub fn finish(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Renders the bar with the bytes parsed and lines read, the current roots per
+/// second and how long the rest of the input should take.
+fn draw(stats: &Stats, total: u64, elapsed: Duration, rate: f64) -> String {
+    // lines are read well ahead of the workers, so only the share of the
+    // queued hosts that has been parsed counts as done
+    let read = stats.bytes.load(Ordering::Relaxed).min(total);
+    let hosts = stats.hosts.load(Ordering::Relaxed);
+    let finished = stats.parsed.load(Ordering::Relaxed)
+        + stats.failed.load(Ordering::Relaxed)
+        + stats.skipped.load(Ordering::Relaxed);
+    let bytes = if hosts == 0 {
+        read
+    } else {
+        (read as f64 * finished.min(hosts) as f64 / hosts as f64) as u64
+    };
+    let done = if total == 0 {
+        1.0
+    } else {
+        bytes as f64 / total as f64
+    };
+    let filled = (done * WIDTH as f64) as usize;
+    let bar: String = (0..WIDTH)
+        .map(|i| match i.cmp(&filled) {
+            std::cmp::Ordering::Less => '=',
+            std::cmp::Ordering::Equal => '>',
+            std::cmp::Ordering::Greater => ' ',
+        })
+        .collect();
+
+    let eta = if bytes == 0 {
+        "-".to_string()
+    } else {
+        let left = elapsed.as_secs_f64() * (total - bytes) as f64 / bytes as f64;
+        duration(left as u64)
+    };
+    format!(
+        "[{}] {:5.1}%  {}/{}  {} lines  {:.0} roots/s  ETA {}",
+        bar,
+        done * 100.0,
+        size(bytes),
+        size(total),
+        stats.lines.load(Ordering::Relaxed),
+        rate,
+        eta
+    )
+}
+
+fn size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.2} {}", size, UNITS[unit])
+    }
+}
+
+fn duration(secs: u64) -> String {
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
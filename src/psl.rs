@@ -0,0 +1,144 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tldextract::{TldExtractor, TldOption};
+
+/// Where the public suffix list is published.
+pub const URL: &str = "https://publicsuffix.org/list/public_suffix_list.dat";
+
+/// A copy of the list built into the binary, so it never needs the network.
+pub const EMBEDDED: &str = include_str!("../data/public_suffix_list.dat");
+
+/// Suffixes used by internal networks that are not on the list.
+pub const INTERNAL: [&str; 9] = [
+    "local",
+    "localdomain",
+    "lan",
+    "home",
+    "home.arpa",
+    "corp",
+    "internal",
+    "intranet",
+    "private",
+];
+
+/// Returns the rules of a list in the `public_suffix_list.dat` format, with
+/// the suffixes of its private section, such as `github.io`, only when
+/// `private` is set.
+pub fn parse(list: &str, private: bool) -> HashSet<String> {
+    let mut rules = HashSet::new();
+    let mut in_private = false;
+    for line in list.lines() {
+        let line = line.trim();
+        if line.starts_with("// ===BEGIN PRIVATE DOMAINS===") {
+            in_private = true;
+        } else if line.starts_with("// ===END PRIVATE DOMAINS===") {
+            in_private = false;
+        }
+        if line.is_empty() || line.starts_with("//") || (in_private && !private) {
+            continue;
+        }
+        // a rule ends at the first whitespace
+        if let Some(rule) = line.split_whitespace().next() {
+            rules.insert(rule.to_lowercase());
+        }
+    }
+    rules
+}
+
+/// Reads a list from a file.
+pub fn read(path: &str) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+    match std::fs::read_to_string(path) {
+        Ok(list) => Ok(list),
+        Err(e) => Err(format!("could not read public suffix list {}: {}", path, e).into()),
+    }
+}
+
+/// How long downloading the list may take.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+async fn download() -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+    info!("downloading the public suffix list from {}", URL);
+    let client = reqwest::Client::builder().timeout(TIMEOUT).build()?;
+    let response = match client.get(URL).send().await {
+        Ok(response) => response,
+        Err(e) => return Err(format!("could not download {}: {}", URL, e).into()),
+    };
+    match response.error_for_status() {
+        Ok(response) => Ok(response.text().await?),
+        Err(e) => Err(format!("could not download {}: {}", URL, e).into()),
+    }
+}
+
+/// Where the downloaded copy of the list is kept, under the user's cache
+/// directory.
+pub fn cache_path() -> Option<PathBuf> {
+    let dir = match std::env::var_os("XDG_CACHE_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+    };
+    Some(dir.join("hostparser").join("public_suffix_list.dat"))
+}
+
+/// Downloads the list into the cache, returning where it was saved.
+pub async fn update() -> Result<PathBuf, Box<dyn Error + Send + Sync + 'static>> {
+    let path = match cache_path() {
+        Some(path) => path,
+        None => return Err("could not find a cache directory, HOME is not set".into()),
+    };
+    let list = download().await?;
+    let save = || -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // a crash while saving never leaves half a list behind
+        let tmp = path.with_extension("dat.tmp");
+        std::fs::write(&tmp, &list)?;
+        std::fs::rename(&tmp, &path)
+    };
+    match save() {
+        Ok(()) => Ok(path),
+        Err(e) => Err(format!("could not save {}: {}", path.display(), e).into()),
+    }
+}
+
+/// Reads the cached copy of the list, if there is one, updating it first
+/// when it is older than `max_age` unless `offline`. A copy that can not be
+/// updated is still used.
+pub async fn cached(max_age: Duration, offline: bool) -> Option<String> {
+    let path = cache_path()?;
+    let modified = std::fs::metadata(&path).ok()?.modified().ok()?;
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default();
+    if age > max_age && !offline {
+        if let Err(e) = update().await {
+            warn!(
+                "{}, using the copy from {} days ago",
+                e,
+                age.as_secs() / 86400
+            );
+        }
+    }
+    std::fs::read_to_string(&path).ok()
+}
+
+/// Builds the extractor matching the rules.
+pub fn extractor(
+    rules: &HashSet<String>,
+) -> Result<TldExtractor, Box<dyn Error + Send + Sync + 'static>> {
+    // tldextract only takes rules from its cache file, kept in a directory
+    // only this user can get at
+    let dir = match tempfile::tempdir() {
+        Ok(dir) => dir,
+        Err(e) => return Err(format!("could not create a temporary directory: {}", e).into()),
+    };
+    let path = dir.path().join("rules.tld_cache");
+    if let Err(e) = std::fs::write(&path, serde_json::to_vec(rules)?) {
+        return Err(format!("could not write {}: {}", path.display(), e).into());
+    }
+    Ok(TldOption::default()
+        .cache_path(&path.to_string_lossy())
+        .build())
+}
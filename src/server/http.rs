@@ -0,0 +1,171 @@
+use async_std::channel::{self, Sender};
+use hyper::body::HttpBody;
+use hyper::header::{self, HeaderValue};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::Value;
+use std::convert::Infallible;
+use std::error::Error;
+use std::net::TcpListener;
+use std::sync::Arc;
+
+use crate::format::{Host, InputFormat};
+use crate::stats::Stats;
+use crate::Job;
+
+/// Binds the listening address up front so a bad address fails at startup.
+pub fn bind(addr: &str) -> Result<TcpListener, Box<dyn Error + Send + Sync + 'static>> {
+    let listener = match TcpListener::bind(addr) {
+        Ok(listener) => listener,
+        Err(e) => return Err(format!("could not listen on {}: {}", addr, e).into()),
+    };
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+/// Answers `POST /parse` requests until the process is stopped. The body is
+/// either read with the input format or, when sent as json, taken as an
+/// array of hosts or an object with a `hosts` array. `GET /metrics` serves
+/// the metrics of the run. Bodies over `max_body` bytes are refused.
+pub async fn serve(
+    listener: TcpListener,
+    tx: Sender<Job>,
+    format: InputFormat,
+    stats: Arc<Stats>,
+    max_body: usize,
+) {
+    let make_service = make_service_fn(move |_| {
+        let tx = tx.clone();
+        let format = format.clone();
+        let stats = stats.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                handle(request, tx.clone(), format.clone(), stats.clone(), max_body)
+            }))
+        }
+    });
+
+    let server = match Server::from_tcp(listener) {
+        Ok(server) => server,
+        Err(e) => {
+            warn!("could not start server: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = server.serve(make_service).await {
+        warn!("server error: {}", e);
+    }
+}
+
+async fn handle(
+    request: Request<Body>,
+    tx: Sender<Job>,
+    mut parser: InputFormat,
+    stats: Arc<Stats>,
+    max_body: usize,
+) -> Result<Response<Body>, Infallible> {
+    if request.uri().path() == "/metrics" {
+        return Ok(super::metrics::response(&stats));
+    }
+    if request.uri().path() != "/parse" {
+        return Ok(respond(StatusCode::NOT_FOUND, "not found\n"));
+    }
+    if request.method() != Method::POST {
+        return Ok(respond(StatusCode::METHOD_NOT_ALLOWED, "use POST\n"));
+    }
+
+    let json = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("json"));
+    let length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if length.is_some_and(|length| length > max_body) {
+        return Ok(too_large(max_body));
+    }
+    let mut body = Vec::with_capacity(length.unwrap_or_default());
+    let mut stream = request.into_body();
+    // a body without a length is cut off once it is too large
+    while let Some(chunk) = stream.data().await {
+        match chunk {
+            Ok(chunk) if body.len() + chunk.len() > max_body => return Ok(too_large(max_body)),
+            Ok(chunk) => body.extend_from_slice(&chunk),
+            Err(e) => {
+                let message = format!("could not read body: {}\n", e);
+                return Ok(respond(StatusCode::BAD_REQUEST, &message));
+            }
+        }
+    }
+    let hosts = if json {
+        match json_hosts(&String::from_utf8_lossy(&body)) {
+            Ok(hosts) => hosts,
+            Err(e) => return Ok(respond(StatusCode::BAD_REQUEST, &format!("{}\n", e))),
+        }
+    } else {
+        super::parse(&mut parser, &body)
+    };
+
+    let (reply_tx, reply_rx) = channel::unbounded::<String>();
+    if !super::submit(&tx, hosts, &reply_tx).await {
+        return Ok(respond(StatusCode::SERVICE_UNAVAILABLE, "shutting down\n"));
+    }
+    drop(reply_tx);
+
+    let mut roots = Vec::new();
+    while let Ok(root) = reply_rx.recv().await {
+        roots.push(root);
+    }
+
+    if json {
+        let mut response = Response::new(Body::from(Value::from(roots).to_string()));
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        Ok(response)
+    } else {
+        let mut text = roots.join("\n");
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        Ok(respond(StatusCode::OK, &text))
+    }
+}
+
+/// Reads the hosts from a json body.
+fn json_hosts(text: &str) -> Result<Vec<Host>, String> {
+    let value: Value = match serde_json::from_str(text) {
+        Ok(value) => value,
+        Err(e) => return Err(format!("invalid json: {}", e)),
+    };
+    let hosts = match &value {
+        Value::Array(hosts) => hosts,
+        Value::Object(object) => match object.get("hosts") {
+            Some(Value::Array(hosts)) => hosts,
+            _ => return Err("expected a hosts array".to_string()),
+        },
+        _ => return Err("expected an array of hosts".to_string()),
+    };
+    hosts
+        .iter()
+        .map(|host| match host.as_str() {
+            Some(host) => Ok(Host::from(host.to_string())),
+            None => Err(format!("expected hosts as strings, got {}", host)),
+        })
+        .collect()
+}
+
+fn too_large(max_body: usize) -> Response<Body> {
+    let message = format!("body is larger than {} bytes\n", max_body);
+    respond(StatusCode::PAYLOAD_TOO_LARGE, &message)
+}
+
+fn respond(status: StatusCode, text: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(text.to_string()));
+    *response.status_mut() = status;
+    response
+}
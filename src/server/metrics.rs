@@ -0,0 +1,49 @@
+use hyper::header::{self, HeaderValue};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use std::convert::Infallible;
+use std::net::TcpListener;
+use std::sync::Arc;
+
+use crate::stats::Stats;
+
+/// Answers `GET /metrics` until the process is stopped.
+pub async fn serve(listener: TcpListener, stats: Arc<Stats>) {
+    let make_service = make_service_fn(move |_| {
+        let stats = stats.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                let stats = stats.clone();
+                async move {
+                    if request.uri().path() != "/metrics" {
+                        let mut response = Response::new(Body::from("not found\n"));
+                        *response.status_mut() = StatusCode::NOT_FOUND;
+                        return Ok::<_, Infallible>(response);
+                    }
+                    Ok(response(&stats))
+                }
+            }))
+        }
+    });
+
+    let server = match Server::from_tcp(listener) {
+        Ok(server) => server,
+        Err(e) => {
+            warn!("could not start metrics server: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = server.serve(make_service).await {
+        warn!("metrics server error: {}", e);
+    }
+}
+
+/// The metrics in the Prometheus text format.
+pub fn response(stats: &Stats) -> Response<Body> {
+    let mut response = Response::new(Body::from(stats.prometheus()));
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; version=0.0.4"),
+    );
+    response
+}
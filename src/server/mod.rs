@@ -0,0 +1,58 @@
+use async_std::channel::{self, Sender};
+use governor::{Quota, RateLimiter};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::format::{Host, InputFormat};
+use crate::stats::Stats;
+use crate::Job;
+
+pub mod http;
+pub mod metrics;
+pub mod unix;
+
+/// Funnels the jobs of every connection into the job queue, which only has a
+/// single producer, applying the rate limit on the way.
+pub fn forward(mut tx: spmc::Sender<Job>, rate: u32, stats: Arc<Stats>) -> Sender<Job> {
+    let (job_tx, job_rx) = channel::unbounded::<Job>();
+    tokio::spawn(async move {
+        let lim = RateLimiter::direct(Quota::per_second(std::num::NonZeroU32::new(rate).unwrap()));
+        while let Ok(job) = job_rx.recv().await {
+            lim.until_ready().await;
+            stats.hosts.fetch_add(1, Ordering::Relaxed);
+            if tx.send(job).is_err() {
+                break;
+            }
+        }
+    });
+    job_tx
+}
+
+/// Returns the hosts in a block of input written by a client.
+pub fn parse(parser: &mut InputFormat, data: &[u8]) -> Vec<Host> {
+    if parser.is_document() {
+        parser.document_hosts(data)
+    } else {
+        String::from_utf8_lossy(data)
+            .lines()
+            .flat_map(|line| parser.hosts(line))
+            .collect()
+    }
+}
+
+/// Queues the hosts as jobs that answer on `reply`.
+pub async fn submit(tx: &Sender<Job>, hosts: Vec<Host>, reply: &Sender<String>) -> bool {
+    for host in hosts {
+        let job = Job {
+            host: Some(host.name),
+            sources: host.sources,
+            mailbox: host.mailbox,
+            reply: Some(reply.clone()),
+            seq: 0,
+        };
+        if tx.send(job).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
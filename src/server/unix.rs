@@ -0,0 +1,84 @@
+use async_std::channel::{self, Sender};
+use async_std::io::prelude::*;
+use async_std::io::BufReader;
+use async_std::os::unix::net::{UnixListener, UnixStream};
+use futures::StreamExt;
+use std::error::Error;
+use std::path::Path;
+
+use crate::format::InputFormat;
+use crate::Job;
+
+/// Binds the socket, replacing one left behind by an earlier run.
+pub async fn bind(path: &Path) -> Result<UnixListener, Box<dyn Error + Send + Sync + 'static>> {
+    if std::fs::symlink_metadata(path).is_ok() {
+        if let Err(e) = std::fs::remove_file(path) {
+            return Err(format!("could not remove {}: {}", path.display(), e).into());
+        }
+    }
+    match UnixListener::bind(path).await {
+        Ok(listener) => Ok(listener),
+        Err(e) => Err(format!("could not listen on {}: {}", path.display(), e).into()),
+    }
+}
+
+/// Accepts connections until the process is stopped. Every line a client
+/// writes is parsed and the root domains are written back on the same
+/// connection, in the order they finish.
+pub async fn serve(listener: UnixListener, tx: Sender<Job>, format: InputFormat) {
+    let mut incoming = listener.incoming();
+    while let Some(stream) = incoming.next().await {
+        match stream {
+            Ok(stream) => {
+                tokio::spawn(handle(stream, tx.clone(), format.clone()));
+            }
+            Err(e) => warn!("error accepting connection: {}", e),
+        }
+    }
+}
+
+/// Serves a single client, closing the connection once it has stopped
+/// writing and every result has been sent.
+async fn handle(stream: UnixStream, tx: Sender<Job>, mut parser: InputFormat) {
+    let (reply_tx, reply_rx) = channel::unbounded::<String>();
+    let mut writer = stream.clone();
+    let replies = tokio::spawn(async move {
+        while let Ok(line) = reply_rx.recv().await {
+            if writer
+                .write_all(format!("{}\n", line).as_bytes())
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    let mut reader = BufReader::new(stream);
+    if parser.is_document() {
+        let mut data = Vec::new();
+        match reader.read_to_end(&mut data).await {
+            Ok(_) => {
+                super::submit(&tx, super::parse(&mut parser, &data), &reply_tx).await;
+            }
+            Err(e) => warn!("error reading connection: {}", e),
+        }
+    } else {
+        let mut lines = reader.lines();
+        while let Some(line) = lines.next().await {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    warn!("error reading connection: {}", e);
+                    break;
+                }
+            };
+            if !super::submit(&tx, parser.hosts(&line), &reply_tx).await {
+                break;
+            }
+        }
+    }
+
+    drop(reply_tx);
+    let _ = replies.await;
+}
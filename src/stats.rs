@@ -0,0 +1,187 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Exit code of a run where an input could not be read.
+pub const EXIT_ERROR: i32 = 1;
+/// Exit code of a run that did not find a single valid host.
+pub const EXIT_NO_HOSTS: i32 = 3;
+/// Exit code of a run where some hosts could not be parsed.
+pub const EXIT_FAILURES: i32 = 4;
+/// Exit code of a run stopped by Ctrl-C or SIGTERM, as shells report it.
+pub const EXIT_STOPPED: i32 = 130;
+
+/// Counts what a run has done, for the summary at the end and the metrics.
+pub struct Stats {
+    start: Instant,
+    /// Lines read from the inputs.
+    pub lines: AtomicU64,
+    /// Bytes of the inputs parsed, including those skipped on resuming.
+    pub bytes: AtomicU64,
+    /// Hosts found in the lines and queued for parsing.
+    pub hosts: AtomicU64,
+    /// Hosts a root domain was extracted from.
+    pub parsed: AtomicU64,
+    /// Hosts without a registrable domain.
+    pub failed: AtomicU64,
+    /// Hosts left out on purpose, such as ip addresses.
+    pub skipped: AtomicU64,
+    /// Inputs that could not be opened or stopped being readable.
+    pub errors: AtomicU64,
+    /// Jobs finished by each worker.
+    workers: Vec<AtomicU64>,
+    /// The distinct root domains, only kept when they are reported.
+    roots: Option<Mutex<HashSet<String>>>,
+}
+
+impl Stats {
+    pub fn new(workers: usize, roots: bool) -> Stats {
+        Stats {
+            start: Instant::now(),
+            lines: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            hosts: AtomicU64::new(0),
+            parsed: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+            skipped: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            workers: (0..workers).map(|_| AtomicU64::new(0)).collect(),
+            roots: roots.then(|| Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// Records what a worker made of a job.
+    pub fn job(&self, worker: usize, root: Option<String>) {
+        match root {
+            Some(root) => {
+                self.parsed.fetch_add(1, Ordering::Relaxed);
+                if let Some(roots) = &self.roots {
+                    roots.lock().unwrap().insert(root);
+                }
+            }
+            None => {
+                self.failed.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        if let Some(jobs) = self.workers.get(worker) {
+            jobs.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Records that a worker left a job out.
+    pub fn skip(&self, worker: usize) {
+        self.skipped.fetch_add(1, Ordering::Relaxed);
+        if let Some(jobs) = self.workers.get(worker) {
+            jobs.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the exit code the run ended with, so scripts can tell a run
+    /// that found nothing from one that worked.
+    pub fn exit_code(&self) -> i32 {
+        if self.errors.load(Ordering::Relaxed) > 0 {
+            EXIT_ERROR
+        } else if self.parsed.load(Ordering::Relaxed) == 0 {
+            EXIT_NO_HOSTS
+        } else if self.failed.load(Ordering::Relaxed) > 0 {
+            EXIT_FAILURES
+        } else {
+            0
+        }
+    }
+
+    fn unique_roots(&self) -> usize {
+        match &self.roots {
+            Some(roots) => roots.lock().unwrap().len(),
+            None => 0,
+        }
+    }
+
+    /// Writes the summary to stderr.
+    pub fn report(&self) {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let hosts = self.hosts.load(Ordering::Relaxed);
+        eprintln!("lines read:     {}", self.lines.load(Ordering::Relaxed));
+        eprintln!("hosts:          {}", hosts);
+        eprintln!("valid hosts:    {}", self.parsed.load(Ordering::Relaxed));
+        eprintln!("parse failures: {}", self.failed.load(Ordering::Relaxed));
+        eprintln!("skipped:        {}", self.skipped.load(Ordering::Relaxed));
+        eprintln!("unique roots:   {}", self.unique_roots());
+        eprintln!("elapsed:        {:.2}s", elapsed);
+        eprintln!(
+            "throughput:     {:.0} hosts/s",
+            hosts as f64 / elapsed.max(0.001)
+        );
+    }
+
+    /// The counters in the Prometheus text format.
+    pub fn prometheus(&self) -> String {
+        let hosts = self.hosts.load(Ordering::Relaxed);
+        let done: u64 = self
+            .workers
+            .iter()
+            .map(|jobs| jobs.load(Ordering::Relaxed))
+            .sum();
+
+        let mut text = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: u64| {
+            let _ = writeln!(text, "# HELP hostparser_{} {}", name, help);
+            let _ = writeln!(text, "# TYPE hostparser_{} {}", name, kind);
+            let _ = writeln!(text, "hostparser_{} {}", name, value);
+        };
+        metric(
+            "lines_total",
+            "counter",
+            "Lines read from the inputs.",
+            self.lines.load(Ordering::Relaxed),
+        );
+        metric("hosts_total", "counter", "Hosts queued for parsing.", hosts);
+        metric(
+            "parsed_total",
+            "counter",
+            "Hosts a root domain was extracted from.",
+            self.parsed.load(Ordering::Relaxed),
+        );
+        metric(
+            "parse_failures_total",
+            "counter",
+            "Hosts without a registrable domain.",
+            self.failed.load(Ordering::Relaxed),
+        );
+        metric(
+            "skipped_total",
+            "counter",
+            "Hosts left out on purpose, such as ip addresses.",
+            self.skipped.load(Ordering::Relaxed),
+        );
+        metric(
+            "unique_roots",
+            "gauge",
+            "Distinct root domains seen.",
+            self.unique_roots() as u64,
+        );
+        metric(
+            "queue_depth",
+            "gauge",
+            "Hosts queued but not parsed yet.",
+            hosts.saturating_sub(done),
+        );
+
+        let _ = writeln!(
+            text,
+            "# HELP hostparser_worker_jobs_total Jobs finished by each worker."
+        );
+        let _ = writeln!(text, "# TYPE hostparser_worker_jobs_total counter");
+        for (worker, jobs) in self.workers.iter().enumerate() {
+            let _ = writeln!(
+                text,
+                "hostparser_worker_jobs_total{{worker=\"{}\"}} {}",
+                worker,
+                jobs.load(Ordering::Relaxed)
+            );
+        }
+        text
+    }
+}
@@ -0,0 +1,155 @@
+/// Characters that pass for another, mapped to the ascii letter they look
+/// like: cyrillic and greek letters, accented latin ones and digits.
+const CONFUSABLES: [(char, char); 40] = [
+    ('а', 'a'),
+    ('е', 'e'),
+    ('о', 'o'),
+    ('р', 'p'),
+    ('с', 'c'),
+    ('х', 'x'),
+    ('у', 'y'),
+    ('і', 'i'),
+    ('ј', 'j'),
+    ('ѕ', 's'),
+    ('ԁ', 'd'),
+    ('ӏ', 'l'),
+    ('һ', 'h'),
+    ('ԛ', 'q'),
+    ('ԝ', 'w'),
+    ('ɡ', 'g'),
+    ('α', 'a'),
+    ('ο', 'o'),
+    ('ν', 'v'),
+    ('τ', 't'),
+    ('ι', 'i'),
+    ('κ', 'k'),
+    ('ρ', 'p'),
+    ('à', 'a'),
+    ('á', 'a'),
+    ('â', 'a'),
+    ('ä', 'a'),
+    ('è', 'e'),
+    ('é', 'e'),
+    ('ë', 'e'),
+    ('í', 'i'),
+    ('ï', 'i'),
+    ('ó', 'o'),
+    ('ö', 'o'),
+    ('ú', 'u'),
+    ('ü', 'u'),
+    ('0', 'o'),
+    ('1', 'l'),
+    ('3', 'e'),
+    ('5', 's'),
+];
+
+/// Letter sequences that pass for a single letter.
+const SEQUENCES: [(&str, &str); 3] = [("rn", "m"), ("vv", "w"), ("cl", "d")];
+
+/// The apex domains of an organization that lookalike domains are searched
+/// for, each split into its name and suffix.
+#[derive(Clone, Debug, Default)]
+pub struct Seeds {
+    seeds: Vec<(String, String)>,
+}
+
+impl Seeds {
+    /// Reads apex domains such as `example.com`.
+    pub fn new<'a>(domains: impl Iterator<Item = &'a str>) -> Seeds {
+        Seeds {
+            seeds: domains
+                .map(|domain| domain.trim().trim_matches('.').to_lowercase())
+                .filter_map(|domain| {
+                    let (name, suffix) = domain.split_once('.')?;
+                    Some((name.to_string(), suffix.to_string()))
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seeds.is_empty()
+    }
+
+    /// Returns the seed a registrable domain, given as its name and suffix,
+    /// is a lookalike of and how, such as `example.com transposition`. The
+    /// seeds themselves are not lookalikes.
+    pub fn check(&self, name: &str, suffix: &str) -> Option<String> {
+        let name = idna::domain_to_unicode(name).0.to_lowercase();
+        self.seeds.iter().find_map(|(seed, seed_suffix)| {
+            let kind = kind(&name, seed)
+                .or_else(|| (name == *seed && suffix != seed_suffix).then_some("suffix"))?;
+            Some(format!("{}.{} {}", seed, seed_suffix, kind))
+        })
+    }
+}
+
+/// How a name imitates a seed, or `None` when it does not or is the seed.
+fn kind(name: &str, seed: &str) -> Option<&'static str> {
+    if name == seed {
+        return None;
+    }
+    if skeleton(name) == skeleton(seed) {
+        return Some("homoglyph");
+    }
+    if name.replace('-', "") == seed.replace('-', "") {
+        return Some("hyphen");
+    }
+    let (a, b): (Vec<char>, Vec<char>) = (name.chars().collect(), seed.chars().collect());
+    if is_transposition(&a, &b) {
+        return Some("transposition");
+    }
+    if is_typo(&a, &b) {
+        return Some("typo");
+    }
+    None
+}
+
+/// The name with every confusable character or sequence replaced by the
+/// letter it passes for.
+fn skeleton(name: &str) -> String {
+    let mut skeleton: String = name
+        .chars()
+        .map(|c| {
+            CONFUSABLES
+                .iter()
+                .find(|(confusable, _)| *confusable == c)
+                .map_or(c, |(_, letter)| *letter)
+        })
+        .collect();
+    for (sequence, letter) in SEQUENCES {
+        skeleton = skeleton.replace(sequence, letter);
+    }
+    skeleton
+}
+
+/// Whether two names differ only by two neighbouring characters swapped.
+fn is_transposition(a: &[char], b: &[char]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff: Vec<usize> = (0..a.len()).filter(|&i| a[i] != b[i]).collect();
+    diff.len() == 2
+        && diff[1] == diff[0] + 1
+        && a[diff[0]] == b[diff[1]]
+        && a[diff[1]] == b[diff[0]]
+}
+
+/// Whether two names differ by a single character added, missing or
+/// replaced, for seeds long enough that this is unlikely by chance.
+fn is_typo(a: &[char], b: &[char]) -> bool {
+    if b.len() < 5 {
+        return false;
+    }
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    match long.len() - short.len() {
+        0 => (0..short.len()).filter(|&i| short[i] != long[i]).count() == 1,
+        1 => {
+            let start = (0..short.len())
+                .find(|&i| short[i] != long[i])
+                .unwrap_or(short.len());
+            short[start..] == long[start + 1..]
+        }
+        _ => false,
+    }
+}